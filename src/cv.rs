@@ -0,0 +1,408 @@
+/// Copyright (c) 2021 Marcos Pontes
+// This code is licensed under MIT license (see LICENSE for details)
+use std::fmt;
+use std::path::Path;
+
+use crate::{
+    error::LtrError,
+    eval::Evaluator,
+    learner::Learner,
+    loader::LtrFormat,
+    split::{Splitter, Stratify},
+    utils::prettytable::{Alignment, Table, TableConfig},
+    DataSet,
+};
+
+/// A fold of a cross-validation: the held-out queries and the ones used to train the model.
+#[derive(Clone, Default)]
+pub struct Fold {
+    /// The training `DataSet`.
+    pub train: DataSet,
+    /// The optional validation `DataSet`, used during the training.
+    pub validation: Option<DataSet>,
+    /// The held-out `DataSet`, used to evaluate the trained model.
+    pub test: DataSet,
+}
+
+/// Partition a `DataSet` into K query-level folds.
+///
+/// Each query is held out in exactly one fold. Optionally, the training queries of
+/// each fold are split again to build an inner validation `DataSet`.
+#[derive(Debug, Clone)]
+pub struct KFold {
+    /// Number of folds.
+    k: usize,
+    /// Splitter used to assign the queries to the folds.
+    splitter: Splitter,
+    /// Fraction of the training queries of each fold used for validation.
+    validation_ratio: Option<f32>,
+}
+
+impl KFold {
+    /// Creates a new `KFold` instance.
+    ///
+    /// # Arguments
+    /// * `k` - The number of folds.
+    /// * `seed` - The seed of the RNG used to shuffle the queries.
+    pub fn new(k: usize, seed: u64) -> KFold {
+        KFold {
+            k,
+            splitter: Splitter::new(seed),
+            validation_ratio: None,
+        }
+    }
+
+    /// Set how the queries are spread across the folds.
+    ///
+    /// # Arguments
+    /// * `stratify` - The stratification strategy.
+    pub fn set_stratify(&mut self, stratify: Stratify) {
+        self.splitter.set_stratify(stratify);
+    }
+
+    /// Hold out a fraction of the training queries of each fold for validation.
+    ///
+    /// # Arguments
+    /// * `ratio` - The fraction of the training queries used for validation.
+    pub fn set_validation_ratio(&mut self, ratio: f32) {
+        self.validation_ratio = Some(ratio);
+    }
+
+    /// Build the folds of a `DataSet`.
+    ///
+    /// # Arguments
+    /// * `dataset` - The `DataSet` to be partitioned.
+    ///
+    /// # Errors
+    /// `LtrError::ConfigurationError` if `k` is lower than 2 or greater than the number
+    /// of queries, or if the validation ratio is invalid.
+    pub fn folds(&self, dataset: &DataSet) -> Result<Vec<Fold>, LtrError> {
+        if self.k < 2 || self.k > dataset.len() {
+            return Err(LtrError::ConfigurationError(
+                "The number of folds must be between 2 and the number of queries.",
+            ));
+        }
+
+        let parts = self
+            .splitter
+            .assign(dataset, &vec![1.0 / self.k as f32; self.k])?;
+        let mut folds = Vec::with_capacity(self.k);
+        for (i, test) in parts.iter().enumerate() {
+            let mut train: Vec<usize> = parts
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .flat_map(|(_, part)| part.iter().copied())
+                .collect();
+            train.sort_unstable();
+            let train = dataset.select(&train)?;
+
+            let (train, validation) = match self.validation_ratio {
+                Some(ratio) => {
+                    let (train, validation) = self.splitter.train_test_split(&train, ratio)?;
+                    (train, Some(validation))
+                }
+                None => (train, None),
+            };
+            folds.push(Fold {
+                train,
+                validation,
+                test: dataset.select(test)?,
+            });
+        }
+        Ok(folds)
+    }
+}
+
+/// Load the pre-made folds of the LETOR and MSLR datasets.
+///
+/// The directory holds one `FoldN` sub-directory per fold (`Fold1`, `Fold2`, ...), each
+/// one with the `train.txt`, `vali.txt` and `test.txt` files. The validation file is optional.
+///
+/// # Arguments
+/// * `directory` - The dataset directory, e.g. `MQ2007` or `MSLR-WEB10K`.
+///
+/// # Errors
+/// `LtrError` if no fold was found or a file could not be loaded.
+pub fn load_letor_folds<F: LtrFormat>(directory: &str) -> Result<Vec<Fold>, LtrError> {
+    let load = |path: &Path| -> Result<DataSet, LtrError> {
+        let path = path
+            .to_str()
+            .ok_or_else(|| LtrError::IOError(format!("Invalid path: {}", path.display())))?;
+        F::load(path)
+    };
+
+    let mut folds = Vec::new();
+    loop {
+        let fold = Path::new(directory).join(format!("Fold{}", folds.len() + 1));
+        if !fold.is_dir() {
+            break;
+        }
+        let validation = fold.join("vali.txt");
+        folds.push(Fold {
+            train: load(&fold.join("train.txt"))?,
+            validation: match validation.is_file() {
+                true => Some(load(&validation)?),
+                false => None,
+            },
+            test: load(&fold.join("test.txt"))?,
+        });
+    }
+
+    if folds.is_empty() {
+        return Err(LtrError::IOError(format!(
+            "No Fold1 directory found in {}",
+            directory
+        )));
+    }
+    Ok(folds)
+}
+
+/// Scores of the model trained on a fold.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FoldScore {
+    /// The (1-based) fold number.
+    pub fold: usize,
+    /// The training score reported by the `Learner`.
+    pub training: f32,
+    /// The validation score reported by the `Learner`, if a validation `DataSet` was used.
+    pub validation: Option<f32>,
+    /// The score on the held-out queries.
+    pub test: f32,
+}
+
+/// Results of a cross-validation, with one `FoldScore` per fold.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrossValidationReport {
+    /// Name of the metric used on the held-out queries.
+    pub metric: String,
+    /// Scores of each fold.
+    pub folds: Vec<FoldScore>,
+}
+
+impl CrossValidationReport {
+    /// Get the average training score over the folds.
+    pub fn mean_training(&self) -> f32 {
+        mean(self.folds.iter().map(|fold| fold.training))
+    }
+
+    /// Get the average validation score over the folds that used a validation `DataSet`.
+    pub fn mean_validation(&self) -> Option<f32> {
+        let scores: Vec<f32> = self.folds.iter().filter_map(|f| f.validation).collect();
+        match scores.is_empty() {
+            true => None,
+            false => Some(mean(scores.into_iter())),
+        }
+    }
+
+    /// Get the average score on the held-out queries over the folds.
+    pub fn mean_test(&self) -> f32 {
+        mean(self.folds.iter().map(|fold| fold.test))
+    }
+}
+
+fn mean(scores: impl Iterator<Item = f32>) -> f32 {
+    let (sum, count) = scores.fold((0.0, 0), |(sum, count), score| (sum + score, count + 1));
+    match count {
+        0 => 0.0,
+        _ => sum / count as f32,
+    }
+}
+
+impl fmt::Display for CrossValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let table = Table::new(TableConfig::new(
+            vec![7, 9, 9, 9],
+            (2, 2),
+            Alignment::Center,
+        ));
+        let validation =
+            |score: Option<f32>| score.map_or("-".to_string(), |s| format!("{:.5}", s));
+
+        writeln!(
+            f,
+            "{}",
+            table.render(
+                vec![
+                    "Fold",
+                    format!("{}-T", self.metric).as_str(),
+                    format!("{}-V", self.metric).as_str(),
+                    format!("{}-Test", self.metric).as_str(),
+                ],
+                None,
+            )
+        )?;
+        for fold in self.folds.iter() {
+            writeln!(
+                f,
+                "{}",
+                table.render(
+                    vec![
+                        fold.fold.to_string().as_str(),
+                        format!("{:.5}", fold.training).as_str(),
+                        validation(fold.validation).as_str(),
+                        format!("{:.5}", fold.test).as_str(),
+                    ],
+                    None,
+                )
+            )?;
+        }
+        write!(
+            f,
+            "{}",
+            table.render(
+                vec![
+                    "Avg.",
+                    format!("{:.5}", self.mean_training()).as_str(),
+                    validation(self.mean_validation()).as_str(),
+                    format!("{:.5}", self.mean_test()).as_str(),
+                ],
+                None,
+            )
+        )
+    }
+}
+
+/// Cross-validate a `Learner`: a fresh model is trained on each fold and evaluated
+/// on its held-out queries.
+///
+/// # Arguments
+/// * `folds` - The folds, built with `KFold::folds` or `load_letor_folds`.
+/// * `build` - Builds a fresh `Learner` given the training `DataSet` of a fold.
+/// * `evaluator` - The metric used on the held-out queries.
+///
+/// # Errors
+/// `LtrError` if a model could not be trained or evaluated.
+pub fn cross_validate<L, B>(
+    folds: &[Fold],
+    mut build: B,
+    evaluator: &dyn Evaluator,
+) -> Result<CrossValidationReport, LtrError>
+where
+    L: Learner,
+    B: FnMut(DataSet) -> L,
+{
+    let mut scores = Vec::with_capacity(folds.len());
+    for (i, fold) in folds.iter().enumerate() {
+        let mut learner = build(fold.train.clone());
+        if let Some(validation) = &fold.validation {
+            learner.set_validation_dataset(validation.clone());
+        }
+        learner.fit()?;
+
+        let score = FoldScore {
+            fold: i + 1,
+            training: learner.score()?,
+            validation: match fold.validation {
+                Some(_) => Some(learner.validation_score()?),
+                None => None,
+            },
+            test: evaluator.evaluate_ranker(&learner, &fold.test)?,
+        };
+        tracing::info!(
+            "Fold {}: {}-Test = {:.5}",
+            score.fold,
+            evaluator.to_string(),
+            score.test
+        );
+        scores.push(score);
+    }
+
+    Ok(CrossValidationReport {
+        metric: evaluator.to_string(),
+        folds: scores,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        datapoint::DataPoint, ensemble::adarank::AdaRank, eval::map::MAP,
+        loader::svmlight::SVMLight, ranklist::RankList,
+    };
+
+    fn dataset(num_queries: usize) -> DataSet {
+        (0..num_queries)
+            .map(|q| {
+                let query_id = format!("q{}", q);
+                RankList::new(vec![
+                    DataPoint::new(1, query_id.as_str(), vec![0.9, 0.1 * q as f32], None),
+                    DataPoint::new(0, query_id.as_str(), vec![0.2, 0.5], None),
+                    DataPoint::new(0, query_id.as_str(), vec![0.1, 0.3], None),
+                ])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_k_fold() {
+        let dataset = dataset(10);
+        let folds = KFold::new(5, 3).folds(&dataset).unwrap();
+        assert_eq!(folds.len(), 5);
+
+        let mut held_out: Vec<_> = folds.iter().flat_map(|f| f.test.query_ids()).collect();
+        held_out.sort();
+        held_out.dedup();
+        assert_eq!(held_out.len(), 10);
+        for fold in folds.iter() {
+            assert_eq!(fold.test.len(), 2);
+            assert_eq!(fold.train.len(), 8);
+            assert!(fold.validation.is_none());
+            for query_id in fold.test.query_ids() {
+                assert!(fold.train.query(query_id.as_str()).is_none());
+            }
+        }
+
+        let mut kfold = KFold::new(5, 3);
+        kfold.set_validation_ratio(0.25);
+        let folds = kfold.folds(&dataset).unwrap();
+        assert_eq!(folds[0].train.len(), 6);
+        assert_eq!(folds[0].validation.as_ref().unwrap().len(), 2);
+
+        assert!(KFold::new(1, 3).folds(&dataset).is_err());
+        assert!(KFold::new(11, 3).folds(&dataset).is_err());
+    }
+
+    #[test]
+    fn test_cross_validate() {
+        let mut kfold = KFold::new(3, 7);
+        kfold.set_validation_ratio(0.2);
+        let folds = kfold.folds(&dataset(9)).unwrap();
+
+        let report = cross_validate(
+            &folds,
+            |train| AdaRank::new(train, Box::new(MAP), 2, 5, 0.0, None, None),
+            &MAP,
+        )
+        .unwrap();
+        assert_eq!(report.metric, "MAP");
+        assert_eq!(report.folds.len(), 3);
+        assert_eq!(report.mean_test(), 1.0);
+        assert!(report.mean_validation().is_some());
+        assert_eq!(format!("{}", report).lines().count(), 5);
+    }
+
+    #[test]
+    fn test_load_letor_folds() {
+        let directory = std::env::temp_dir().join("adarank_test_letor_folds");
+        let line = "1 qid:10 1:0.5 2:0.1\n0 qid:10 1:0.2 2:0.3\n";
+        for fold in 1..=2 {
+            let fold = directory.join(format!("Fold{}", fold));
+            std::fs::create_dir_all(&fold).unwrap();
+            std::fs::write(fold.join("train.txt"), line).unwrap();
+            std::fs::write(fold.join("test.txt"), line).unwrap();
+        }
+        std::fs::write(directory.join("Fold1").join("vali.txt"), line).unwrap();
+
+        let folds = load_letor_folds::<SVMLight>(directory.to_str().unwrap());
+        std::fs::remove_dir_all(&directory).unwrap();
+        let folds = folds.unwrap();
+        assert_eq!(folds.len(), 2);
+        assert_eq!(folds[0].train.len(), 1);
+        assert!(folds[0].validation.is_some());
+        assert!(folds[1].validation.is_none());
+
+        assert!(load_letor_folds::<SVMLight>("/nonexistent/letor").is_err());
+    }
+}
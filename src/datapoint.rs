@@ -1,12 +1,322 @@
 /// Copyright (c) 2021 Marcos Pontes
 // This code is licensed under MIT license (see LICENSE for details)
+use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::fmt;
 use std::ops::Index;
+use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 
-use crate::{error::LtrError, Feature};
+use crate::{dataset::FeatureNames, error::LtrError, Feature};
+
+/// A relevance label, either an integer grade or a real-valued target such as a
+/// click-through rate. It's used to build `DataPoint`s from both kinds of labels,
+/// which are stored as `f32`.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Default, Serialize, Deserialize)]
+pub struct Relevance(pub f32);
+
+impl From<u8> for Relevance {
+    fn from(label: u8) -> Self {
+        Relevance(label as f32)
+    }
+}
+
+impl From<f32> for Relevance {
+    fn from(label: f32) -> Self {
+        Relevance(label)
+    }
+}
+
+/// The identifier of a query.
+///
+/// Query ids are strings, so non-numeric ids from logs are supported. Cloning a
+/// `QueryId` is cheap: every clone shares the same string, so the `DataPoint`s
+/// of a query loaded together share a single (interned) identifier.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct QueryId(Arc<str>);
+
+impl QueryId {
+    /// Creates a new `QueryId`.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The identifier of the query.
+    pub fn new(id: &str) -> QueryId {
+        QueryId(Arc::from(id))
+    }
+
+    /// Returns the identifier as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for QueryId {
+    fn default() -> Self {
+        QueryId::new("0")
+    }
+}
+
+impl From<&str> for QueryId {
+    fn from(id: &str) -> Self {
+        QueryId::new(id)
+    }
+}
+
+impl From<String> for QueryId {
+    fn from(id: String) -> Self {
+        QueryId(Arc::from(id))
+    }
+}
+
+/// Numeric query ids, as in the SVM-Light format, are stored as their decimal representation.
+impl From<u32> for QueryId {
+    fn from(id: u32) -> Self {
+        QueryId::from(id.to_string())
+    }
+}
+
+impl PartialEq<str> for QueryId {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for QueryId {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl fmt::Display for QueryId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// How the features of a `DataPoint` are stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum FeatureStorage {
+    /// Every feature value is stored, including zeros.
+    #[default]
+    Dense,
+    /// Only the `(index, value)` pairs present in the data are stored.
+    Sparse,
+}
+
+/// The feature values of a `DataPoint`.
+///
+/// Features are indexed from 1 in both representations. The dimension of the features
+/// is the highest index, and missing features of a sparse row are zero.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Features {
+    /// Every feature value, the feature `i` being at the position `i - 1`.
+    Dense(Vec<Feature>),
+    /// `(index, value)` pairs, sorted by index and without duplicates.
+    /// Use `Features::sparse` to build it from unsorted pairs.
+    Sparse(Vec<(usize, Feature)>),
+}
+
+/// The value of the features missing in a sparse row.
+static ZERO: Feature = 0.0;
+
+impl Features {
+    /// Creates sparse features from `(index, value)` pairs.
+    /// The pairs are sorted by index and, for duplicated indices, the last value is kept.
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - The `(index, value)` pairs, with indices starting at 1.
+    ///
+    /// # Errors
+    ///
+    /// `LtrError::FeatureIndexOutOfBounds` if an index is 0.
+    pub fn sparse(mut entries: Vec<(usize, Feature)>) -> Result<Features, LtrError> {
+        if entries.iter().any(|&(index, _)| index == 0) {
+            return Err(LtrError::FeatureIndexOutOfBounds(0));
+        }
+        // The sort is stable, so the last duplicated value is the last of each run.
+        entries.sort_by_key(|&(index, _)| index);
+        let mut sorted: Vec<(usize, Feature)> = Vec::with_capacity(entries.len());
+        for entry in entries {
+            match sorted.last_mut() {
+                Some(last) if last.0 == entry.0 => *last = entry,
+                _ => sorted.push(entry),
+            }
+        }
+        Ok(Features::Sparse(sorted))
+    }
+
+    /// Returns the dimension of the features, i.e., the highest feature index.
+    pub fn len(&self) -> usize {
+        match self {
+            Features::Dense(values) => values.len(),
+            Features::Sparse(entries) => entries.last().map_or(0, |&(index, _)| index),
+        }
+    }
+
+    /// Returns whether there are no features.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns whether the features are stored sparsely.
+    pub fn is_sparse(&self) -> bool {
+        matches!(self, Features::Sparse(_))
+    }
+
+    /// Get a specific feature, with the index starting at 1.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the feature to be returned.
+    ///
+    /// # Returns
+    ///
+    /// The feature at the given index, which is zero for missing sparse features.
+    pub fn get(&self, index: usize) -> Result<&Feature, LtrError> {
+        match self {
+            Features::Dense(values) => feature_at(values, index),
+            Features::Sparse(entries) => {
+                if index == 0 || index > self.len() {
+                    return Err(LtrError::FeatureIndexOutOfBounds(index));
+                }
+                match entries.binary_search_by_key(&index, |&(i, _)| i) {
+                    Ok(position) => Ok(&entries[position].1),
+                    Err(_) => Ok(&ZERO),
+                }
+            }
+        }
+    }
+
+    /// Set a specific feature, with the index starting at 1.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the feature to be updated, within the dimension.
+    /// * `feature` - The new feature value.
+    pub fn set(&mut self, index: usize, feature: Feature) -> Result<(), LtrError> {
+        if index == 0 || index > self.len() {
+            return Err(LtrError::FeatureIndexOutOfBounds(index));
+        }
+        match self {
+            Features::Dense(values) => values[index - 1] = feature,
+            Features::Sparse(entries) => match entries.binary_search_by_key(&index, |&(i, _)| i) {
+                Ok(position) => entries[position].1 = feature,
+                Err(position) => entries.insert(position, (index, feature)),
+            },
+        }
+        Ok(())
+    }
+
+    /// Add a feature after the last one, increasing the dimension.
+    ///
+    /// # Arguments
+    ///
+    /// * `feature` - The feature to be added.
+    pub fn push(&mut self, feature: Feature) {
+        let index = self.len() + 1;
+        match self {
+            Features::Dense(values) => values.push(feature),
+            Features::Sparse(entries) => entries.push((index, feature)),
+        }
+    }
+
+    /// Iterate over the stored `(index, value)` pairs, in index order.
+    /// Dense features yield every feature, while sparse features skip the missing ones.
+    pub fn iter(&self) -> Box<dyn Iterator<Item = (usize, Feature)> + '_> {
+        match self {
+            Features::Dense(values) => {
+                Box::new(values.iter().enumerate().map(|(i, v)| (i + 1, *v)))
+            }
+            Features::Sparse(entries) => Box::new(entries.iter().copied()),
+        }
+    }
+
+    /// Returns every feature value, the feature `i` being at the position `i - 1`.
+    pub fn to_dense(&self) -> Vec<Feature> {
+        match self {
+            Features::Dense(values) => values.clone(),
+            Features::Sparse(entries) => {
+                let mut values = vec![0.0; self.len()];
+                for &(index, value) in entries.iter() {
+                    values[index - 1] = value;
+                }
+                values
+            }
+        }
+    }
+
+    /// Convert the features to the given storage.
+    /// Zeros are not stored when converting dense features to sparse.
+    ///
+    /// # Arguments
+    ///
+    /// * `storage` - The target storage.
+    pub fn to_storage(&self, storage: FeatureStorage) -> Features {
+        match (self, storage) {
+            (Features::Dense(values), FeatureStorage::Sparse) => {
+                let mut entries: Vec<(usize, Feature)> = values
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, value)| **value != 0.0)
+                    .map(|(i, value)| (i + 1, *value))
+                    .collect();
+                // Keep the dimension of the dense features.
+                if values.last() == Some(&0.0) {
+                    entries.push((values.len(), 0.0));
+                }
+                Features::Sparse(entries)
+            }
+            (Features::Sparse(_), FeatureStorage::Dense) => Features::Dense(self.to_dense()),
+            _ => self.clone(),
+        }
+    }
+}
+
+impl Default for Features {
+    fn default() -> Self {
+        Features::Dense(Vec::new())
+    }
+}
+
+impl From<Vec<Feature>> for Features {
+    fn from(values: Vec<Feature>) -> Self {
+        Features::Dense(values)
+    }
+}
+
+/// Features are equal when they have the same dimension and values,
+/// regardless of how they are stored.
+impl PartialEq for Features {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && (1..=self.len()).all(|i| self.get(i) == other.get(i))
+    }
+}
+
+impl PartialEq<Vec<Feature>> for Features {
+    fn eq(&self, other: &Vec<Feature>) -> bool {
+        self.to_dense() == *other
+    }
+}
+
+impl fmt::Display for Features {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Features::Dense(values) => write!(f, "{:?}", values),
+            Features::Sparse(entries) => {
+                let entries: Vec<String> = entries
+                    .iter()
+                    .map(|(index, value)| format!("{}:{:?}", index, value))
+                    .collect();
+                write!(f, "{{{}}}", entries.join(", "))
+            }
+        }
+    }
+}
 
 /// A DataPoint is a single training instance (Like in RankLib).
 /// A DataPoint represents a pair `[item, query]` extracted
@@ -18,26 +328,47 @@ use crate::{error::LtrError, Feature};
 /// and `<value_i>` is the value of the feature.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DataPoint {
-    /// The label of the DataPoint.
-    label: u8,
+    /// The relevance label of the DataPoint.
+    label: f32,
     /// The query id of the DataPoint.
     /// This is the identifier of the query that the DataPoint belongs to.
-    query_id: u32,
-    /// The features of the DataPoint.
-    /// This is a vector of `Feature`s.
-    features: Vec<Feature>,
+    query_id: QueryId,
+    /// Optional identifier of the document, e.g. for run files.
+    #[serde(default)]
+    doc_id: Option<String>,
+    /// The features of the DataPoint, stored densely or sparsely.
+    features: Features,
     /// Optional description of the DataPoint.
     /// This is a string that can be used to describe the DataPoint.
     description: Option<String>,
 }
 
+/// Get a specific feature of a raw feature vector, with the same indexing as
+/// `DataPoint::get_feature`: the index starts at 1.
+///
+/// # Arguments
+///
+/// * `features` - The feature values.
+/// * `index` - The (1-based) index of the feature to be returned.
+///
+/// # Returns
+///
+/// The feature at the given index.
+pub fn feature_at(features: &[Feature], index: usize) -> Result<&Feature, LtrError> {
+    if index == 0 || index > features.len() {
+        return Err(LtrError::FeatureIndexOutOfBounds(index));
+    }
+    Ok(&features[index - 1])
+}
+
 impl DataPoint {
     /// Creates an empty DataPoint
     pub fn empty() -> DataPoint {
         DataPoint {
-            label: 0,
-            query_id: 0,
-            features: Vec::new(),
+            label: 0.0,
+            query_id: QueryId::default(),
+            doc_id: None,
+            features: Features::default(),
             description: None,
         }
     }
@@ -46,57 +377,91 @@ impl DataPoint {
     ///
     /// # Arguments
     ///
-    /// * `label` - The label of the DataPoint.
-    /// * `query_id` - The query id of the DataPoint.
+    /// * `label` - The label of the DataPoint, either an integer grade or a real value.
+    /// * `query_id` - The query id of the DataPoint, either numeric or a string.
     /// * `features` - The features of the DataPoint.
     /// * `description` - Optional description of the DataPoint.
     pub fn new(
-        label: u8,
-        query_id: u32,
+        label: impl Into<Relevance>,
+        query_id: impl Into<QueryId>,
         features: Vec<Feature>,
         description: Option<&str>,
     ) -> DataPoint {
         DataPoint {
-            label,
-            query_id,
-            features,
+            label: label.into().0,
+            query_id: query_id.into(),
+            doc_id: None,
+            features: Features::Dense(features),
             description: description.map(|s| s.to_string()), // None or Some(s)
         }
     }
 
     /// Returns the label of the DataPoint.
-    pub fn get_label(&self) -> u8 {
+    pub fn get_label(&self) -> f32 {
         self.label
     }
 
     /// Returns the query id of the DataPoint.
-    pub fn get_query_id(&self) -> u32 {
-        self.query_id
+    pub fn get_query_id(&self) -> &QueryId {
+        &self.query_id
+    }
+
+    /// Returns the document id of the DataPoint.
+    pub fn get_doc_id(&self) -> Option<&str> {
+        self.doc_id.as_deref()
     }
 
-    /// Returns the features of the DataPoint.
-    pub fn get_features(&self) -> &Vec<Feature> {
+    /// Returns the features of the DataPoint, as they are stored.
+    pub fn features(&self) -> &Features {
         &self.features
     }
 
+    /// Returns every feature value of the DataPoint, the feature `i` being at the
+    /// position `i - 1`. Sparse features are expanded into a new vector: use
+    /// `DataPoint::features` to read them without copying.
+    pub fn get_features(&self) -> Cow<'_, Vec<Feature>> {
+        match &self.features {
+            Features::Dense(values) => Cow::Borrowed(values),
+            Features::Sparse(_) => Cow::Owned(self.features.to_dense()),
+        }
+    }
+
     /// Get a specific feature of the DataPoint.
     ///
     /// # Arguments
     ///
     /// * `index` - The index of the feature to be returned. The index starts at 1, because
-    /// it's common to use the feature label as the index when indexing the features. For example,
-    /// the SVM-Light format indexes each feature with a label starting at 1. In order to avoid
-    /// confusion, the index starts at 1.
+    ///   it's common to use the feature label as the index when indexing the features. For example,
+    ///   the SVM-Light format indexes each feature with a label starting at 1. In order to avoid
+    ///   confusion, the index starts at 1.
     ///
     ///
     /// # Returns
     ///
     /// The feature at the given index.
     pub fn get_feature(&self, index: usize) -> Result<&Feature, LtrError> {
-        if index == 0 || index > self.features.len() {
-            return Err(LtrError::FeatureIndexOutOfBounds(index));
-        }
-        Ok(&self.features[index - 1])
+        self.features.get(index)
+    }
+
+    /// Returns a feature of the DataPoint given its name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the feature, e.g. `BM25-title`.
+    /// * `names` - The feature-name dictionary of the dataset.
+    ///
+    /// # Errors
+    ///
+    /// `LtrError::UnknownFeature` if the name is not in the dictionary.
+    pub fn get_named_feature(
+        &self,
+        name: &str,
+        names: &FeatureNames,
+    ) -> Result<&Feature, LtrError> {
+        let index = names
+            .index(name)
+            .ok_or_else(|| LtrError::UnknownFeature(name.to_string()))?;
+        self.get_feature(index)
     }
 
     /// Returns the description of the DataPoint.
@@ -109,8 +474,8 @@ impl DataPoint {
     /// # Arguments
     ///
     /// * `label` - The new label of the DataPoint.
-    pub fn set_label(&mut self, label: u8) {
-        self.label = label;
+    pub fn set_label(&mut self, label: impl Into<Relevance>) {
+        self.label = label.into().0;
     }
 
     /// Set the query id of the DataPoint.
@@ -118,8 +483,17 @@ impl DataPoint {
     /// # Arguments
     ///
     /// * `query_id` - The new query id of the DataPoint.
-    pub fn set_query_id(&mut self, query_id: u32) {
-        self.query_id = query_id;
+    pub fn set_query_id(&mut self, query_id: impl Into<QueryId>) {
+        self.query_id = query_id.into();
+    }
+
+    /// Set the document id of the DataPoint.
+    ///
+    /// # Arguments
+    ///
+    /// * `doc_id` - The new document id of the DataPoint.
+    pub fn set_doc_id(&mut self, doc_id: &str) {
+        self.doc_id = Some(doc_id.to_string());
     }
 
     /// Add a feature to the DataPoint.
@@ -141,12 +515,7 @@ impl DataPoint {
     /// * `index` - The index of the feature to be updated.
     /// * `feature` - The new feature value.
     pub fn set_feature(&mut self, index: usize, feature: Feature) -> Result<(), LtrError> {
-        // Sanity check
-        if index > self.features.len() {
-            return Err(LtrError::FeatureIndexOutOfBounds(index));
-        }
-        self.features[index - 1] = feature;
-        Ok(())
+        self.features.set(index, feature)
     }
 
     /// Set all feature values.
@@ -155,10 +524,30 @@ impl DataPoint {
     ///
     /// * `features` - The new feature values.
     pub fn set_features(&mut self, features: Vec<Feature>) -> Result<(), LtrError> {
-        self.features = features;
+        self.features = Features::Dense(features);
         Ok(())
     }
 
+    /// Set all feature values from sparse `(index, value)` pairs.
+    /// See `Features::sparse`.
+    ///
+    /// # Arguments
+    ///
+    /// * `entries` - The `(index, value)` pairs, with indices starting at 1.
+    pub fn set_sparse_features(&mut self, entries: Vec<(usize, Feature)>) -> Result<(), LtrError> {
+        self.features = Features::sparse(entries)?;
+        Ok(())
+    }
+
+    /// Convert the features of the DataPoint to the given storage.
+    ///
+    /// # Arguments
+    ///
+    /// * `storage` - The target storage.
+    pub fn set_feature_storage(&mut self, storage: FeatureStorage) {
+        self.features = self.features.to_storage(storage);
+    }
+
     /// Set the description of the DataPoint.
     ///
     /// # Arguments
@@ -173,7 +562,7 @@ impl fmt::Display for DataPoint {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
-            "DataPoint: label={}, query_id={}, features={:?}, description={:?}",
+            "DataPoint: label={}, query_id={}, features={}, description={:?}",
             self.label, self.query_id, self.features, self.description
         )
     }
@@ -188,17 +577,13 @@ impl PartialEq for DataPoint {
     fn eq(&self, other: &Self) -> bool {
         self.label == other.label && self.query_id == other.query_id
     }
-
-    fn ne(&self, other: &Self) -> bool {
-        !self.eq(other)
-    }
 }
 
 /// A DataPoint can be partial compared using its label.
 /// This is useful when sorting DataPoints with the same query_id.
 impl PartialOrd for DataPoint {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.label.cmp(&other.label))
+        self.label.partial_cmp(&other.label)
     }
 }
 
@@ -237,9 +622,9 @@ mod tests {
     fn test_data_point_new() {
         let features: Vec<Feature> = vec![1.2, 3.4, 5.6];
         let mut data_point = dp!(1, 2, features.clone(), "This is a test");
-        assert_eq!(data_point.get_label(), 1);
-        assert_eq!(data_point.get_query_id(), 2);
-        assert_eq!(data_point.get_features(), &features);
+        assert_eq!(data_point.get_label(), 1.0);
+        assert_eq!(data_point.get_query_id(), "2");
+        assert_eq!(*data_point.get_features(), features);
         assert_eq!(
             data_point.get_description(),
             Some(&"This is a test".to_string())
@@ -272,8 +657,8 @@ mod tests {
         data_point.set_query_id(4);
         data_point.set_description("This is another test");
 
-        assert_eq!(data_point.get_label(), 2);
-        assert_eq!(data_point.get_query_id(), 4);
+        assert_eq!(data_point.get_label(), 2.0);
+        assert_eq!(data_point.get_query_id(), "4");
         assert_eq!(
             data_point.get_description(),
             Some(&"This is another test".to_string())
@@ -281,11 +666,12 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::assertions_on_constants, clippy::unnecessary_cast)]
     fn test_update_features() {
         let mut mydp = dp!(1, 2, vec![1.2, 3.4, 5.6], "This is a test");
 
         // Assert that the features are correct
-        assert_eq!(mydp.get_features(), &vec![1.2, 3.4, 5.6]);
+        assert_eq!(*mydp.get_features(), vec![1.2, 3.4, 5.6]);
 
         match mydp.get_feature(0) {
             Ok(_) => assert!(false),
@@ -309,4 +695,108 @@ mod tests {
 
         assert!(mydp > snapshot);
     }
+
+    #[test]
+    fn test_sparse_features() {
+        let mut sparse = dp!(1, 2, vec![]);
+        sparse
+            .set_sparse_features(vec![(7, 0.5), (2, 1.5), (7, 2.5)])
+            .unwrap();
+
+        assert!(sparse.features().is_sparse());
+        assert_eq!(sparse.get_features().len(), 7);
+        assert_eq!(sparse.get_feature(2), Ok(&1.5));
+        assert_eq!(sparse.get_feature(3), Ok(&0.0));
+        assert_eq!(sparse.get_feature(7), Ok(&2.5));
+        assert_eq!(
+            sparse.get_feature(8),
+            Err(LtrError::FeatureIndexOutOfBounds(8))
+        );
+        assert_eq!(sparse[2], 1.5);
+        assert_eq!(
+            format!("{}", sparse),
+            "DataPoint: label=1, query_id=2, features={2:1.5, 7:2.5}, description=None"
+        );
+
+        sparse.set_feature(3, 4.0).unwrap();
+        sparse.add_feature(1.0).unwrap();
+        assert!(sparse.set_feature(0, 1.0).is_err());
+        assert_eq!(
+            sparse.features(),
+            &vec![0.0, 1.5, 4.0, 0.0, 0.0, 0.0, 2.5, 1.0]
+        );
+        assert_eq!(
+            *sparse.get_features(),
+            vec![0.0, 1.5, 4.0, 0.0, 0.0, 0.0, 2.5, 1.0]
+        );
+        assert_eq!(
+            sparse.features().iter().collect::<Vec<_>>(),
+            vec![(2, 1.5), (3, 4.0), (7, 2.5), (8, 1.0)]
+        );
+
+        // Equal features regardless of the storage.
+        let mut dense = sparse.clone();
+        dense.set_feature_storage(FeatureStorage::Dense);
+        assert!(!dense.features().is_sparse());
+        assert_eq!(dense.features(), sparse.features());
+
+        let trailing_zero = Features::Dense(vec![1.0, 0.0]).to_storage(FeatureStorage::Sparse);
+        assert_eq!(trailing_zero.len(), 2);
+        assert!(Features::sparse(vec![(0, 1.0)]).is_err());
+
+        // Sparse and dense rows have distinct serialized forms.
+        let json = serde_json::to_string(sparse.features()).unwrap();
+        assert_eq!(json, "[[2,1.5],[3,4.0],[7,2.5],[8,1.0]]");
+        let restored: Features = serde_json::from_str(&json).unwrap();
+        assert!(restored.is_sparse());
+        let restored: Features = serde_json::from_str("[1.0,2.0]").unwrap();
+        assert!(!restored.is_sparse());
+    }
+
+    #[test]
+    fn test_named_features() {
+        let data_point = dp!(1, "q1", vec![0.5, 2.5]);
+        let names = FeatureNames::from(vec!["TF-title".to_string(), "BM25-title".to_string()]);
+        assert_eq!(data_point.get_named_feature("BM25-title", &names), Ok(&2.5));
+        assert_eq!(
+            data_point.get_named_feature("PageRank", &names),
+            Err(LtrError::UnknownFeature("PageRank".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_identifiers() {
+        let mut data_point = dp!(1, "q-17", vec![1.0], "free text");
+        assert_eq!(data_point.get_query_id(), "q-17");
+        assert_eq!(data_point.get_doc_id(), None);
+
+        data_point.set_doc_id("GX000-00-0000000");
+        data_point.set_query_id(12);
+        assert_eq!(data_point.get_doc_id(), Some("GX000-00-0000000"));
+        assert_eq!(data_point.get_query_id(), &QueryId::from("12"));
+        assert_eq!(data_point.get_description().unwrap(), "free text");
+
+        // Clones share the same identifier.
+        let query_id = QueryId::new("q-17");
+        let clone = query_id.clone();
+        assert!(Arc::ptr_eq(&query_id.0, &clone.0));
+        assert_eq!(format!("{}", clone), "q-17");
+
+        let json = serde_json::to_string(&data_point).unwrap();
+        assert!(json.contains("\"query_id\":\"12\""));
+        let restored: DataPoint = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.get_doc_id(), Some("GX000-00-0000000"));
+    }
+
+    #[test]
+    fn test_real_valued_labels() {
+        let mut data_point = dp!(0.35, 1, vec![1.0]);
+        assert_eq!(data_point.get_label(), 0.35);
+
+        data_point.set_label(-1.5);
+        assert_eq!(data_point.get_label(), -1.5);
+        data_point.set_label(3);
+        assert_eq!(data_point.get_label(), 3.0);
+        assert!(data_point > dp!(0.5, 1, vec![1.0]));
+    }
 }
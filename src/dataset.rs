@@ -0,0 +1,554 @@
+/// Copyright (c) 2021 Marcos Pontes
+// This code is licensed under MIT license (see LICENSE for details)
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::ops::Deref;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{datapoint::QueryId, error::LtrError, ranklist::RankList};
+
+/// A dictionary of feature names, mapping (1-based) feature indices to names
+/// such as `BM25-title`, as documented by the LETOR and MSLR datasets.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FeatureNames {
+    names: BTreeMap<usize, String>,
+}
+
+impl FeatureNames {
+    /// Creates an empty `FeatureNames` dictionary.
+    pub fn new() -> FeatureNames {
+        FeatureNames::default()
+    }
+
+    /// Parse a feature-name mapping, with one `<index> <name>` pair per line.
+    /// The index may be followed by a space, a tab or a colon, and the name is
+    /// the rest of the line. Empty lines and lines starting with `#` are skipped.
+    ///
+    /// # Arguments
+    /// * `buffer` - The content of the mapping.
+    ///
+    /// # Errors
+    /// `LtrError::ParseError` if a line has no valid index or no name.
+    pub fn parse(buffer: &str) -> Result<FeatureNames, LtrError> {
+        let mut names = FeatureNames::new();
+        for line in buffer.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let split = line
+                .find(|c: char| c.is_whitespace() || c == ':')
+                .ok_or(LtrError::ParseError("Missing the feature name."))?;
+            let index = line[..split]
+                .parse::<usize>()
+                .ok()
+                .filter(|&index| index > 0)
+                .ok_or(LtrError::ParseError("Invalid feature index."))?;
+            let name = line[split + 1..].trim();
+            if name.is_empty() {
+                return Err(LtrError::ParseError("Missing the feature name."));
+            }
+            names.insert(index, name);
+        }
+        Ok(names)
+    }
+
+    /// Load a feature-name mapping file. See `FeatureNames::parse`.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the file.
+    ///
+    /// # Errors
+    /// `LtrError` if the file could not be read or parsed.
+    pub fn load(path: &str) -> Result<FeatureNames, LtrError> {
+        let buffer = std::fs::read_to_string(path).map_err(|e| LtrError::IOError(e.to_string()))?;
+        FeatureNames::parse(&buffer)
+    }
+
+    /// Set the name of a feature.
+    ///
+    /// # Arguments
+    /// * `index` - The (1-based) index of the feature.
+    /// * `name` - The name of the feature.
+    pub fn insert(&mut self, index: usize, name: &str) {
+        self.names.insert(index, name.to_string());
+    }
+
+    /// Get the name of a feature, if known.
+    pub fn name(&self, index: usize) -> Option<&str> {
+        self.names.get(&index).map(|name| name.as_str())
+    }
+
+    /// Get the index of a feature given its name.
+    pub fn index(&self, name: &str) -> Option<usize> {
+        self.names
+            .iter()
+            .find(|(_, n)| n.as_str() == name)
+            .map(|(&index, _)| index)
+    }
+
+    /// Get a printable label for a feature: its name if known, or its index.
+    pub fn label(&self, index: usize) -> String {
+        self.name(index)
+            .map_or_else(|| index.to_string(), |name| name.to_string())
+    }
+
+    /// Get the number of named features.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Check whether no feature is named.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Iterate over the `(index, name)` pairs, in index order.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.names
+            .iter()
+            .map(|(&index, name)| (index, name.as_str()))
+    }
+}
+
+/// Names given in order: the first name is the feature 1.
+impl From<Vec<String>> for FeatureNames {
+    fn from(names: Vec<String>) -> Self {
+        FeatureNames {
+            names: names
+                .into_iter()
+                .enumerate()
+                .map(|(i, n)| (i + 1, n))
+                .collect(),
+        }
+    }
+}
+
+/// Descriptive information about a `DataSet`, as returned by `DataSet::metadata`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DataSetMetadata {
+    /// Name of each feature.
+    pub feature_names: Option<FeatureNames>,
+    /// Number of features, i.e., the highest feature index of the `DataSet`.
+    pub num_features: usize,
+    /// Lowest and highest relevance labels of the `DataSet`.
+    pub label_scale: Option<(f32, f32)>,
+    /// The file the `DataSet` was loaded from.
+    pub source: Option<String>,
+}
+
+/// A `DataSet` is the collection of `RankList`s used to train and evaluate `Ranker`s,
+/// one `RankList` per query, along with its `DataSetMetadata`.
+///
+/// It dereferences to a slice of `RankList`s, so it can be iterated, indexed and
+/// sliced as a `Vec<RankList>`. Existing vectors are converted with `DataSet::from`.
+///
+/// The number of features and the label scale are computed from the `RankList`s when
+/// requested, so they stay correct when the `RankList`s are modified in place.
+/// A `DataSet` is serialized along with its metadata, and can also be deserialized
+/// from a plain sequence of `RankList`s.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(into = "DataSetRepr", from = "DataSetFormat")]
+pub struct DataSet {
+    /// The `RankList`s of the `DataSet`.
+    ranklists: Vec<RankList>,
+    /// Name of each feature.
+    feature_names: Option<FeatureNames>,
+    /// The file the `DataSet` was loaded from.
+    source: Option<String>,
+    /// Lowest number of features, e.g. kept from the `DataSet` a subset was selected from.
+    min_features: usize,
+    /// Position of the `RankList` of each query.
+    queries: HashMap<QueryId, usize>,
+}
+
+/// Serialized form of a `DataSet`.
+#[derive(Clone, Serialize, Deserialize)]
+struct DataSetRepr {
+    ranklists: Vec<RankList>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    feature_names: Option<FeatureNames>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    #[serde(default)]
+    num_features: usize,
+}
+
+/// Accepted serialized forms of a `DataSet`: with its metadata, or the plain sequence
+/// of `RankList`s of the former `DataSet` alias.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DataSetFormat {
+    DataSet(DataSetRepr),
+    RankLists(Vec<RankList>),
+}
+
+impl From<DataSet> for DataSetRepr {
+    fn from(dataset: DataSet) -> Self {
+        DataSetRepr {
+            num_features: dataset.num_features(),
+            ranklists: dataset.ranklists,
+            feature_names: dataset.feature_names,
+            source: dataset.source,
+        }
+    }
+}
+
+impl From<DataSetFormat> for DataSet {
+    fn from(format: DataSetFormat) -> Self {
+        match format {
+            DataSetFormat::DataSet(repr) => {
+                let mut dataset = DataSet::from(repr.ranklists);
+                dataset.feature_names = repr.feature_names;
+                dataset.source = repr.source;
+                dataset.min_features = repr.num_features;
+                dataset
+            }
+            DataSetFormat::RankLists(ranklists) => DataSet::from(ranklists),
+        }
+    }
+}
+
+impl DataSet {
+    /// Creates an empty `DataSet`.
+    pub fn new() -> DataSet {
+        DataSet::default()
+    }
+
+    /// Get the `RankList`s of the `DataSet`.
+    pub fn ranklists(&self) -> &[RankList] {
+        &self.ranklists
+    }
+
+    /// Get the `RankList` at the given position.
+    pub fn get(&self, index: usize) -> Option<&RankList> {
+        self.ranklists.get(index)
+    }
+
+    /// Get the `RankList` of a query.
+    ///
+    /// # Arguments
+    /// * `query_id` - The query id, e.g. `"10"` or `"q-17"`.
+    ///
+    /// # Returns
+    /// The `RankList` of the query, or `None` if the query is not in the `DataSet`.
+    pub fn query(&self, query_id: &str) -> Option<&RankList> {
+        self.queries
+            .get(&QueryId::from(query_id))
+            .map(|&index| &self.ranklists[index])
+    }
+
+    /// Get the query ids of the `DataSet`, in order.
+    pub fn query_ids(&self) -> Vec<QueryId> {
+        self.ranklists
+            .iter()
+            .filter_map(|ranklist| ranklist.query_id())
+            .collect()
+    }
+
+    /// Add a `RankList` to the `DataSet`.
+    ///
+    /// # Arguments
+    /// * `ranklist` - The `RankList` to be added.
+    pub fn push(&mut self, ranklist: RankList) {
+        if let Some(query_id) = ranklist.query_id() {
+            self.queries.entry(query_id).or_insert(self.ranklists.len());
+        }
+        self.ranklists.push(ranklist);
+    }
+
+    /// Get the metadata of the `DataSet`, computed from its current `RankList`s.
+    pub fn metadata(&self) -> DataSetMetadata {
+        DataSetMetadata {
+            feature_names: self.feature_names.clone(),
+            num_features: self.num_features(),
+            label_scale: self.label_scale(),
+            source: self.source.clone(),
+        }
+    }
+
+    /// Get the number of features of the `DataSet`, i.e., the highest feature index.
+    pub fn num_features(&self) -> usize {
+        self.ranklists
+            .iter()
+            .flat_map(|ranklist| {
+                ranklist
+                    .data_points()
+                    .iter()
+                    .map(|dp| dp.features().len())
+                    .max()
+            })
+            .fold(self.min_features, usize::max)
+    }
+
+    /// Get the lowest and highest relevance labels of the `DataSet`.
+    pub fn label_scale(&self) -> Option<(f32, f32)> {
+        let mut scale: Option<(f32, f32)> = None;
+        for ranklist in self.ranklists.iter() {
+            for dp in ranklist.data_points().iter() {
+                let label = dp.get_label();
+                scale = Some(scale.map_or((label, label), |(min, max)| {
+                    (min.min(label), max.max(label))
+                }));
+            }
+        }
+        scale
+    }
+
+    /// Get the file the `DataSet` was loaded from.
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_deref()
+    }
+
+    /// Set the file the `DataSet` was loaded from.
+    pub fn set_source(&mut self, source: &str) {
+        self.source = Some(source.to_string());
+    }
+
+    /// Get the name of each feature, if known.
+    pub fn feature_names(&self) -> Option<&FeatureNames> {
+        self.feature_names.as_ref()
+    }
+
+    /// Attach a feature-name dictionary to the `DataSet`.
+    ///
+    /// # Arguments
+    /// * `names` - The name of each feature, e.g. loaded with `FeatureNames::load`.
+    pub fn set_feature_names(&mut self, names: FeatureNames) {
+        self.feature_names = Some(names);
+    }
+
+    /// Get the index of a feature given its name.
+    ///
+    /// # Errors
+    /// `LtrError::UnknownFeature` if no feature-name dictionary is attached or the
+    /// name is not in it.
+    pub fn feature_index(&self, name: &str) -> Result<usize, LtrError> {
+        self.feature_names()
+            .and_then(|names| names.index(name))
+            .ok_or_else(|| LtrError::UnknownFeature(name.to_string()))
+    }
+
+    /// Build a `DataSet` with some of the `RankList`s of this one, keeping its
+    /// feature names, number of features and source.
+    ///
+    /// # Arguments
+    /// * `indices` - The positions of the `RankList`s to keep, in the new order.
+    ///
+    /// # Errors
+    /// `LtrError::RankListIndexOutOfBounds` if an index is out of bounds.
+    pub fn select(&self, indices: &[usize]) -> Result<DataSet, LtrError> {
+        let mut dataset = DataSet::new();
+        for &index in indices {
+            let ranklist = self
+                .ranklists
+                .get(index)
+                .ok_or(LtrError::RankListIndexOutOfBounds(index))?;
+            dataset.push(ranklist.clone());
+        }
+        dataset.min_features = self.num_features();
+        dataset.feature_names = self.feature_names.clone();
+        dataset.source = self.source.clone();
+        Ok(dataset)
+    }
+
+    /// Split the `DataSet` by query id.
+    ///
+    /// # Arguments
+    /// * `query_ids` - The queries of the first `DataSet`.
+    ///
+    /// # Returns
+    /// The `RankList`s of the given queries and the remaining ones, in the original order.
+    ///
+    /// # Errors
+    /// `LtrError::ConfigurationError` if a query is not in the `DataSet`.
+    pub fn partition_queries(&self, query_ids: &[&str]) -> Result<(DataSet, DataSet), LtrError> {
+        let mut selected = HashSet::new();
+        for query_id in query_ids {
+            let index = self
+                .queries
+                .get(&QueryId::from(*query_id))
+                .ok_or(LtrError::ConfigurationError("Unknown query id."))?;
+            selected.insert(*index);
+        }
+        let (chosen, rest): (Vec<usize>, Vec<usize>) =
+            (0..self.ranklists.len()).partition(|index| selected.contains(index));
+        Ok((self.select(&chosen)?, self.select(&rest)?))
+    }
+
+    /// Build a `DataSet` without the queries that have no relevant document, which
+    /// evaluators always score 0. See `RankList::has_relevant`.
+    ///
+    /// # Returns
+    /// The remaining `RankList`s in the original order, keeping the feature names,
+    /// number of features and source.
+    pub fn drop_queries_without_relevant(&self) -> DataSet {
+        let indices: Vec<usize> = (0..self.ranklists.len())
+            .filter(|&index| self.ranklists[index].has_relevant())
+            .collect();
+        self.select(&indices).expect("the indices are in bounds")
+    }
+
+    /// Consume the `DataSet`, returning its `RankList`s.
+    pub fn into_ranklists(self) -> Vec<RankList> {
+        self.ranklists
+    }
+}
+
+impl Deref for DataSet {
+    type Target = [RankList];
+
+    fn deref(&self) -> &Self::Target {
+        &self.ranklists
+    }
+}
+
+impl<'a> IntoIterator for &'a DataSet {
+    type Item = &'a RankList;
+    type IntoIter = std::slice::Iter<'a, RankList>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.ranklists.iter()
+    }
+}
+
+impl IntoIterator for DataSet {
+    type Item = RankList;
+    type IntoIter = std::vec::IntoIter<RankList>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.ranklists.into_iter()
+    }
+}
+
+impl FromIterator<RankList> for DataSet {
+    fn from_iter<T: IntoIterator<Item = RankList>>(iter: T) -> Self {
+        let mut dataset = DataSet::new();
+        for ranklist in iter {
+            dataset.push(ranklist);
+        }
+        dataset
+    }
+}
+
+impl From<Vec<RankList>> for DataSet {
+    fn from(ranklists: Vec<RankList>) -> Self {
+        ranklists.into_iter().collect()
+    }
+}
+
+impl From<DataSet> for Vec<RankList> {
+    fn from(dataset: DataSet) -> Self {
+        dataset.ranklists
+    }
+}
+
+impl fmt::Display for DataSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "DataSet object with {} queries and {} features",
+            self.ranklists.len(),
+            self.num_features()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{datapoint::DataPoint, rl};
+
+    #[test]
+    fn test_dataset() {
+        let mut dataset = DataSet::from(vec![
+            rl!((0, "q1", vec![0.1, 0.5]), (2, "q1", vec![0.9, 0.1])),
+            rl!((1, "q2", vec![0.2, 0.3, 0.7])),
+        ]);
+        dataset.set_source("train.txt");
+
+        assert_eq!(dataset.len(), 2);
+        assert_eq!(dataset.num_features(), 3);
+        assert_eq!(dataset.label_scale(), Some((0.0, 2.0)));
+        assert_eq!(dataset.source(), Some("train.txt"));
+        assert_eq!(dataset.feature_names(), None);
+        assert_eq!(
+            format!("{}", dataset),
+            "DataSet object with 2 queries and 3 features"
+        );
+
+        assert_eq!(dataset.query("q2").unwrap().len(), 1);
+        assert!(dataset.query("q3").is_none());
+        assert_eq!(
+            dataset.query_ids(),
+            vec![QueryId::from("q1"), QueryId::from("q2")]
+        );
+        assert_eq!(dataset[0].len(), 2);
+        assert_eq!(
+            dataset.iter().map(|ranklist| ranklist.len()).sum::<usize>(),
+            3
+        );
+
+        dataset.push(rl!((-1.0, "q3", vec![0.1])));
+        assert_eq!(dataset.label_scale(), Some((-1.0, 2.0)));
+        assert_eq!(dataset.query("q3").unwrap().len(), 1);
+
+        assert!(!dataset[2].has_relevant());
+        let judged = dataset.drop_queries_without_relevant();
+        assert_eq!(judged.query_ids(), vec!["q1", "q2"]);
+        assert_eq!(judged.source(), Some("train.txt"));
+
+        // The metadata follows the in-place changes of the RankLists.
+        dataset[2]
+            .set(0, DataPoint::new(3.0, "q3", vec![0.1, 0.2, 0.3, 0.4], None))
+            .unwrap();
+        assert_eq!(dataset.num_features(), 4);
+        assert_eq!(dataset.label_scale(), Some((0.0, 3.0)));
+        assert_eq!(dataset.metadata().label_scale, Some((0.0, 3.0)));
+        assert!(format!("{:?}", dataset).starts_with("DataSet"));
+
+        let json = serde_json::to_string(&dataset).unwrap();
+        let parsed: DataSet = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.metadata(), dataset.metadata());
+        assert_eq!(parsed.query("q3").unwrap().len(), 1);
+
+        // Plain sequences of RankLists, as serialized before `DataSet` was a struct.
+        let ranklists: Vec<RankList> = dataset.into();
+        assert_eq!(ranklists.len(), 3);
+        let json = serde_json::to_string(&ranklists).unwrap();
+        let parsed: DataSet = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.len(), 3);
+        assert_eq!(parsed.source(), None);
+    }
+
+    #[test]
+    fn test_feature_names() {
+        let names = FeatureNames::parse(
+            "# LETOR 4.0 features\n\
+             1 TF-title\n\
+             17:BM25-title\n\
+             \n\
+             18\tBM25 of the body",
+        )
+        .unwrap();
+        assert_eq!(names.len(), 3);
+        assert_eq!(names.name(17), Some("BM25-title"));
+        assert_eq!(names.name(18), Some("BM25 of the body"));
+        assert_eq!(names.index("TF-title"), Some(1));
+        assert_eq!(names.label(17), "BM25-title");
+        assert_eq!(names.label(2), "2");
+        assert!(FeatureNames::parse("x BM25").is_err());
+        assert!(FeatureNames::parse("3").is_err());
+
+        let mut dataset = DataSet::from(vec![rl!((0, "q1", vec![0.1, 0.5]))]);
+        assert!(dataset.feature_index("TF-title").is_err());
+        dataset.set_feature_names(names);
+        assert_eq!(dataset.feature_index("BM25-title"), Ok(17));
+        let index = dataset.feature_index("TF-title").unwrap();
+        assert_eq!(*dataset[0].get(0).unwrap().get_feature(index).unwrap(), 0.1);
+
+        let names = FeatureNames::from(vec!["a".to_string(), "b".to_string()]);
+        assert_eq!(names.iter().collect::<Vec<_>>(), vec![(1, "a"), (2, "b")]);
+    }
+}
@@ -4,17 +4,117 @@
 use std::collections::HashSet;
 
 use colored::Color;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
 
-use super::weak::WeakRanker;
+use super::{index::FeatureIndex, weak::WeakRanker};
 
 use crate::{
+    dataset::FeatureNames,
+    error::LtrError,
     eval::Evaluator,
-    learner::{DatasetConfigurable, FeaturesConfigurable, Learner, MetricConfigurable},
-    ranker::Ranker,
+    history::{IterationRecord, IterationStatus, TrainingHistory},
+    learner::{
+        CallbackConfigurable, CallbackControl, DatasetConfigurable, FeaturesConfigurable,
+        FileSerializable, IterationContext, Learner, MetricConfigurable, TrainingCallback,
+    },
+    normalize::Normalizer,
+    ranker::{Ranker, RankingPolicy},
+    ranklist::RankList,
     utils::prettytable::{Alignment, Table, TableConfig},
     DataSet,
 };
 
+/// Strategy to initialize the sample weights when continuing the training
+/// of a model with `AdaRank::warm_start`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleWeightsInit {
+    /// Keep the sample weights of the previous training process.
+    /// The training dataset must have the same number of queries.
+    Restore,
+    /// Recompute the sample weights from the performance of the current model.
+    Recompute,
+    /// Use a uniform distribution, as in a new training process.
+    Uniform,
+}
+
+/// Serializable state of a trained `AdaRank` model.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AdaRankModel {
+    /// `WeakRanker`s of the ensemble.
+    rankers: Vec<WeakRanker>,
+    /// The amount of say for each stump of the ensemble.
+    ranker_weights: Vec<f32>,
+    /// Sample's weights at the end of the training process.
+    sample_weights: Vec<f32>,
+    /// Name of the features, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    feature_names: Option<FeatureNames>,
+    /// Fitted normalization applied to the features before scoring.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    normalizer: Option<Normalizer>,
+}
+
+/// Serializable state of an `AdaRank` training process.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AdaRankCheckpoint {
+    /// Current state of the ensemble.
+    model: AdaRankModel,
+    /// Best `WeakRanker`s found so far.
+    best_rankers: Vec<WeakRanker>,
+    /// Weights of the best `WeakRanker`s found so far.
+    best_weights: Vec<f32>,
+    /// Sample weights following the best model found so far.
+    #[serde(default)]
+    best_sample_weights: Vec<f32>,
+    /// Iteration in which the best model was found.
+    best_iteration: Option<usize>,
+    /// Validation score of the best model.
+    best_validation_score: f32,
+    /// Features already saturated.
+    used_features: HashSet<usize>,
+    /// Current number of consecutive feature selection.
+    consecutive_selections: usize,
+    /// Previous selected feature.
+    previous_feature: usize,
+    /// Previous training score.
+    previous_training_score: f32,
+    /// Previous validation score.
+    previous_validation_score: f32,
+    /// Number of consecutive iterations without validation improvement.
+    stale_iterations: usize,
+    /// Number of iterations left in the training process.
+    remaining_iterations: u64,
+    /// Records of the iterations performed so far.
+    history: TrainingHistory,
+    /// Parameters of the training process.
+    params: AdaRankParams,
+}
+
+/// Parameters of an `AdaRank` training process, which must not change when it is resumed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct AdaRankParams {
+    /// Name of the training metric.
+    metric: String,
+    /// Name of the validation metric.
+    validation_metric: String,
+    /// Maximum number of consecutive feature selection.
+    max_consecutive_selections: usize,
+    /// Tolerance criteria to stop the algorithm.
+    tolerance: f32,
+    /// Subset of features used in the model.
+    features: Vec<usize>,
+    /// Early stopping patience.
+    patience: Option<usize>,
+    /// Minimum validation improvement to be considered as progress.
+    min_delta: f32,
+    /// How NaN scores and ties are handled.
+    ranking_policy: RankingPolicy,
+    /// Whether the training queries without relevant documents get no sample weight.
+    skip_queries_without_relevant: bool,
+}
+
 /// The basic idea of AdaRank is constructing “weak rankers” repeatedly based on reweighted
 /// training queries and linearly combining the weak rankers for making ranking predictions.
 /// In learning, AdaRank minimizes a loss function directly defined on performance measures.
@@ -26,6 +126,8 @@ pub struct AdaRank {
     validation_dataset: Option<DataSet>,
     /// Pointer to a evaluator.
     scorer: Box<dyn Evaluator>,
+    /// Optional evaluator used on the validation dataset. When `None`, `scorer` is used.
+    validation_scorer: Option<Box<dyn Evaluator>>,
     /// The number of iterations to be performed.
     pub iter: u64,
     /// Maximum number of consecutive feature selection
@@ -54,12 +156,37 @@ pub struct AdaRank {
     ranker_weights: Vec<f32>,
     /// Best model's weights. It indicates the importance of each stump during the training process.
     best_weights: Vec<f32>,
+    /// Sample weights following the best model, restored with it at the end of the training.
+    best_sample_weights: Vec<f32>,
     /// Best `WeakRanker`s of the ensemble.
     rankers: Vec<WeakRanker>,
     /// Best `WeakRanker`s found during the training process.
     best_rankers: Vec<WeakRanker>,
     /// Features already saturated.
     used_features: HashSet<usize>,
+    /// Number of iterations without validation improvement tolerated before stopping.
+    /// When `None`, early stopping is disabled.
+    patience: Option<usize>,
+    /// Minimum validation improvement to be considered as progress.
+    min_delta: f32,
+    /// Iteration in which the best model was found.
+    best_iteration: Option<usize>,
+    /// Number of consecutive iterations without validation improvement.
+    stale_iterations: usize,
+    /// Path and frequency (in iterations) of the training checkpoints.
+    checkpoint: Option<(String, usize)>,
+    /// Per-iteration records of the last training process.
+    history: TrainingHistory,
+    /// Callbacks invoked during the training process.
+    callbacks: Vec<Box<dyn TrainingCallback>>,
+    /// How NaN scores and ties are handled when ranking and evaluating.
+    ranking_policy: RankingPolicy,
+    /// Whether the training queries without relevant documents get no sample weight.
+    skip_queries_without_relevant: bool,
+    /// Name of the features, used in the logs and in the saved models.
+    feature_names: Option<FeatureNames>,
+    /// Fitted normalization applied to the features before scoring.
+    normalizer: Option<Normalizer>,
     /// Results table.
     table: Table,
 }
@@ -69,7 +196,7 @@ impl AdaRank {
     /// Create a new `AdaRank` instance.
     ///
     pub fn new(
-        training_dataset: DataSet,
+        training_dataset: impl Into<DataSet>,
         scorer: Box<dyn Evaluator>,
         iter: u64,
         max_consecutive_selections: usize,
@@ -77,25 +204,29 @@ impl AdaRank {
         features: Option<Vec<usize>>,
         validation_dataset: Option<DataSet>,
     ) -> Self {
+        let training_dataset: DataSet = training_dataset.into();
         let rankers = Vec::new();
         let best_rankers = Vec::new();
         let ranker_weights = Vec::new();
         let best_weights = Vec::new();
+        let best_sample_weights = Vec::new();
         let used_features = HashSet::new();
 
         let sample_weights = AdaRank::initialize_weights(training_dataset.len());
-        let tcfg = AdaRank::table_config();
+        let feature_names = training_dataset.feature_names().cloned();
+        let tcfg = AdaRank::table_config(feature_names.as_ref());
 
-        // If None, use all features -> range(0, training_dataset[0].len())
+        // If None, use all features -> range(1, num_features + 1)
         let features_used = match features {
             Some(ft) => ft,
-            None => (1..training_dataset[0].len() + 1).collect(),
+            None => (1..training_dataset.num_features() + 1).collect(),
         };
 
         AdaRank {
             training_dataset,
             validation_dataset,
             scorer,
+            validation_scorer: None,
             iter,
             max_consecutive_selections,
             consecutive_selections: 0,
@@ -109,15 +240,222 @@ impl AdaRank {
             sample_weights,
             ranker_weights,
             best_weights,
+            best_sample_weights,
             rankers,
             best_rankers,
             used_features,
+            patience: None,
+            min_delta: 0.0,
+            best_iteration: None,
+            stale_iterations: 0,
+            checkpoint: None,
+            history: TrainingHistory::default(),
+            callbacks: Vec::new(),
+            ranking_policy: RankingPolicy::default(),
+            skip_queries_without_relevant: false,
+            feature_names,
+            normalizer: None,
             table: Table::new(tcfg),
         }
     }
 
-    fn table_config() -> TableConfig {
-        TableConfig::new(vec![7, 8, 9, 9, 9, 9, 9], (2, 2), Alignment::Center)
+    /// Enable early stopping on the validation dataset.
+    ///
+    /// The training stops when the validation score does not improve by more than
+    /// `min_delta` during `patience` consecutive iterations. Early stopping has no
+    /// effect when no validation dataset is provided.
+    ///
+    /// # Arguments
+    /// * `patience` - Number of iterations without improvement before stopping.
+    /// * `min_delta` - Minimum validation improvement to be considered as progress.
+    pub fn set_early_stopping(&mut self, patience: usize, min_delta: f32) {
+        self.patience = Some(patience);
+        self.min_delta = min_delta;
+    }
+
+    /// Set the evaluator used on the validation dataset.
+    /// By default, the training metric is also used for validation.
+    ///
+    /// # Arguments
+    /// * `metric` - The metric used to evaluate the validation dataset.
+    pub fn set_validation_metric(&mut self, metric: Box<dyn Evaluator>) {
+        self.validation_scorer = Some(metric);
+    }
+
+    /// Set how NaN scores and tied scores are handled when the training and validation
+    /// datasets are ranked. By default, NaN scores are ranked last and ties keep the
+    /// original order of the documents.
+    ///
+    /// # Arguments
+    /// * `policy` - The ranking policy used during the training.
+    pub fn set_ranking_policy(&mut self, policy: RankingPolicy) {
+        self.ranking_policy = policy;
+    }
+
+    /// Set the training dataset, e.g. before a `warm_start`. Its feature names, if any,
+    /// replace the ones of the model.
+    ///
+    /// # Arguments
+    /// * `dataset` - The training dataset, a `DataSet` or a `Vec<RankList>`.
+    pub fn set_train_dataset(&mut self, dataset: impl Into<DataSet>) {
+        let dataset = dataset.into();
+        if let Some(names) = dataset.feature_names() {
+            self.set_feature_names(names.clone());
+        }
+        self.training_dataset = dataset;
+    }
+
+    /// Set the validation dataset.
+    ///
+    /// # Arguments
+    /// * `dataset` - The validation dataset, a `DataSet` or a `Vec<RankList>`.
+    pub fn set_validation_dataset(&mut self, dataset: impl Into<DataSet>) {
+        self.validation_dataset = Some(dataset.into());
+    }
+
+    /// Give no sample weight to the training queries without relevant documents, so
+    /// they neither drive the weak ranker selection nor count in the training score.
+    /// Evaluators score these queries 0 whatever the ranking. To drop them from the
+    /// dataset instead, use `DataSet::drop_queries_without_relevant`.
+    ///
+    /// # Arguments
+    /// * `skip` - Whether the queries without relevant documents are skipped.
+    pub fn set_skip_queries_without_relevant(&mut self, skip: bool) {
+        self.skip_queries_without_relevant = skip;
+        self.sample_weights = self.uniform_weights();
+    }
+
+    /// Get the iteration in which the final model was taken.
+    ///
+    /// # Returns
+    /// The (zero-based) iteration of the best model, or `None` if the model was not fitted.
+    pub fn best_iteration(&self) -> Option<usize> {
+        self.best_iteration
+    }
+
+    /// Get the history of the last training process, with one record per iteration.
+    pub fn history(&self) -> &TrainingHistory {
+        &self.history
+    }
+
+    /// Set the name of the features, shown in the training logs and in the feature
+    /// importance, and saved with the model. By default, the feature names of the
+    /// training dataset are used.
+    ///
+    /// # Arguments
+    /// * `names` - The feature-name dictionary.
+    pub fn set_feature_names(&mut self, names: FeatureNames) {
+        self.table = Table::new(AdaRank::table_config(Some(&names)));
+        self.feature_names = Some(names);
+    }
+
+    /// Get the name of the features, if known.
+    pub fn feature_names(&self) -> Option<&FeatureNames> {
+        self.feature_names.as_ref()
+    }
+
+    /// Attach a fitted global `Normalizer` to the model. The model then scores raw
+    /// features: they are normalized with the fitted statistics before being weighted,
+    /// both during the training and at inference time. The normalizer is saved with the model.
+    ///
+    /// Since a global normalization keeps the order of the documents on each feature,
+    /// the training dataset must not be normalized beforehand.
+    ///
+    /// # Arguments
+    /// * `normalizer` - The fitted normalizer, e.g. fitted on the training dataset.
+    ///
+    /// # Errors
+    /// `LtrError::ConfigurationError` if the normalizer is per-query or was not fitted.
+    pub fn set_normalizer(&mut self, normalizer: Normalizer) -> Result<(), LtrError> {
+        if !normalizer.is_fitted() {
+            return Err(LtrError::ConfigurationError(
+                "Only fitted global normalizers can be attached to a model.",
+            ));
+        }
+        self.normalizer = Some(normalizer);
+        Ok(())
+    }
+
+    /// Get the normalizer attached to the model, if any.
+    pub fn normalizer(&self) -> Option<&Normalizer> {
+        self.normalizer.as_ref()
+    }
+
+    /// Normalize a feature value with the attached normalizer, if any.
+    fn normalize(&self, index: usize, value: f32) -> f32 {
+        match &self.normalizer {
+            Some(normalizer) => normalizer.normalize_value(index, value),
+            None => value,
+        }
+    }
+
+    /// Get the name of a feature, or its index if the name is unknown.
+    fn feature_label(&self, index: usize) -> String {
+        self.feature_names
+            .as_ref()
+            .map_or_else(|| index.to_string(), |names| names.label(index))
+    }
+
+    /// Get the importance of each feature in the ensemble, i.e., the sum of the
+    /// weights of the `WeakRanker`s built on it.
+    ///
+    /// # Returns
+    /// The `(feature, importance)` pairs, from the most to the least important.
+    pub fn feature_importance(&self) -> Vec<(usize, f32)> {
+        let mut importance: Vec<(usize, f32)> = Vec::new();
+        for (ranker, weight) in self.rankers.iter().zip(self.ranker_weights.iter()) {
+            match importance.iter_mut().find(|(f, _)| *f == ranker.feature_id) {
+                Some((_, total)) => *total += weight,
+                None => importance.push((ranker.feature_id, *weight)),
+            }
+        }
+        importance.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+        importance
+    }
+
+    /// Log the importance of each feature in the ensemble, with the feature names if known.
+    pub fn log_feature_importance(&self) {
+        let importance = self.feature_importance();
+        let width = importance
+            .iter()
+            .map(|(feature, _)| self.feature_label(*feature).len())
+            .max()
+            .unwrap_or(0)
+            .max(7);
+        let table_logger = Table::new(TableConfig::new(vec![width, 10], (2, 2), Alignment::Center));
+
+        tracing::info!(
+            "{}",
+            table_logger.render(vec!["Feature", "Importance"], Some(Color::Cyan))
+        );
+        for (feature, weight) in importance {
+            tracing::info!(
+                "{}",
+                table_logger.render(
+                    vec![
+                        self.feature_label(feature).as_str(),
+                        format!("{:.5}", weight).as_str(),
+                    ],
+                    None,
+                )
+            );
+        }
+    }
+
+    fn validation_scorer(&self) -> &dyn Evaluator {
+        self.validation_scorer.as_deref().unwrap_or(&*self.scorer)
+    }
+
+    fn table_config(feature_names: Option<&FeatureNames>) -> TableConfig {
+        let feature_width = feature_names
+            .and_then(|names| names.iter().map(|(_, name)| name.len()).max())
+            .unwrap_or(0)
+            .max(8);
+        TableConfig::new(
+            vec![7, feature_width, 9, 9, 9, 9, 9],
+            (2, 2),
+            Alignment::Center,
+        )
     }
 
     fn debug_header(&self) -> String {
@@ -127,7 +465,7 @@ impl AdaRank {
                 "Feature",
                 format!("{}-T", self.scorer.to_string()).as_str(),
                 "Improve-T",
-                format!("{}-V", self.scorer.to_string()).as_str(),
+                format!("{}-V", self.validation_scorer().to_string()).as_str(),
                 "Improve-V",
                 "Status",
             ],
@@ -135,25 +473,16 @@ impl AdaRank {
         )
     }
 
-    fn debug_line(
-        &self,
-        current_it: usize,
-        feature: usize,
-        training_score: f32,
-        improvement: f32,
-        validation_score: f32,
-        validation_improvement: f32,
-        status: &str,
-    ) -> String {
+    fn debug_line(&self, record: &IterationRecord) -> String {
         self.table.render(
             vec![
-                format!("{}", current_it).as_str(),
-                format!("{}", feature).as_str(),
-                format!("{:.5}", training_score).as_str(),
-                format!("{:.5}", improvement).as_str(),
-                format!("{:.5}", validation_score).as_str(),
-                format!("{:.5}", validation_improvement).as_str(),
-                status,
+                format!("{}", record.iteration).as_str(),
+                self.feature_label(record.feature).as_str(),
+                format!("{:.5}", record.training_score).as_str(),
+                format!("{:.5}", record.training_improvement).as_str(),
+                format!("{:.5}", record.validation_score.unwrap_or(0.0)).as_str(),
+                format!("{:.5}", record.validation_improvement.unwrap_or(0.0)).as_str(),
+                format!("{}", record.status).as_str(),
             ],
             None,
         )
@@ -161,7 +490,7 @@ impl AdaRank {
 
     /// Get the training results summary.
     pub fn log_results(&self) {
-        let results_config = TableConfig::new(vec![9, 9], (2, 2), Alignment::Center);
+        let results_config = TableConfig::new(vec![9, 9, 9], (2, 2), Alignment::Center);
         let table_logger = Table::new(results_config);
 
         tracing::info!(
@@ -169,7 +498,8 @@ impl AdaRank {
             table_logger.render(
                 vec![
                     format!("{}-T", self.scorer.to_string()).as_str(),
-                    format!("{}-V", self.scorer.to_string()).as_str(),
+                    format!("{}-V", self.validation_scorer().to_string()).as_str(),
+                    "Best-Iter",
                 ],
                 Some(Color::Cyan),
             )
@@ -180,6 +510,9 @@ impl AdaRank {
                 vec![
                     format!("{:.5}", self.score_training).as_str(),
                     format!("{:.5}", self.score_validation).as_str(),
+                    self.best_iteration
+                        .map_or("-".to_string(), |it| it.to_string())
+                        .as_str(),
                 ],
                 None,
             )
@@ -195,25 +528,264 @@ impl AdaRank {
         weights
     }
 
-    fn evaluate_weak_ranker(&self, ranker: &WeakRanker) -> f32 {
-        let mut score = 0.0;
-        for (i, sample) in self.training_dataset.iter().enumerate() {
-            ranker.rank(sample);
-            score += self.scorer.evaluate_ranklist(sample) * self.sample_weights[i];
+    /// Get which training queries are weighted. When the queries without relevant
+    /// documents are skipped, they are not, unless no query has relevant documents.
+    fn weighted_queries(&self) -> Vec<bool> {
+        let weighted: Vec<bool> = self
+            .training_dataset
+            .iter()
+            .map(|ranklist| !self.skip_queries_without_relevant || ranklist.has_relevant())
+            .collect();
+        match weighted.contains(&true) {
+            true => weighted,
+            false => vec![true; weighted.len()],
         }
-        score
     }
 
-    fn select_weak_ranker(&mut self) -> Option<WeakRanker> {
+    /// Uniform sample weights over the weighted training queries.
+    fn uniform_weights(&self) -> Vec<f32> {
+        let weighted = self.weighted_queries();
+        let count = weighted.iter().filter(|&&w| w).count();
+        weighted
+            .iter()
+            .map(|&w| if w { 1.0 / count as f32 } else { 0.0 })
+            .collect()
+    }
+
+    /// Compute the sample weights from the performance of the current model,
+    /// as in the AdaRank paper: `P(q) = exp(-E(q)) / sum(exp(-E(q')))`.
+    fn recompute_weights(&self) -> Result<Vec<f32>, LtrError> {
+        let mut weights = Vec::with_capacity(self.training_dataset.len());
+        for (ranklist, weighted) in self.training_dataset.iter().zip(self.weighted_queries()) {
+            match weighted {
+                true => weights.push((-self.evaluate_ranklist(ranklist)?).exp()),
+                false => weights.push(0.0),
+            }
+        }
+        let total: f32 = weights.iter().sum();
+        weights.iter_mut().for_each(|weight| *weight /= total);
+        Ok(weights)
+    }
+
+    /// Evaluate the current model on a single training `RankList`, following the ranking policy.
+    /// Metrics with a cutoff only rank the top documents.
+    fn evaluate_ranklist(&self, ranklist: &RankList) -> Result<f32, LtrError> {
+        self.scorer
+            .evaluate_ranker_on(self, &ranklist.data_points(), &self.ranking_policy)
+    }
+
+    /// Evaluate the current model on the weighted training queries.
+    fn evaluate_training_dataset(&self) -> Result<f32, LtrError> {
+        if !self.skip_queries_without_relevant {
+            return self.scorer.evaluate_ranker_with(
+                self,
+                &self.training_dataset,
+                &self.ranking_policy,
+            );
+        }
+        let mut score = 0.0f32;
+        let mut count = 0;
+        for (ranklist, weighted) in self.training_dataset.iter().zip(self.weighted_queries()) {
+            if weighted {
+                score += self.evaluate_ranklist(ranklist)?;
+                count += 1;
+            }
+        }
+        match count {
+            0 => Err(LtrError::EvaluationError(
+                "Error in AdaRank: the training dataset is empty.",
+            )),
+            _ => Ok(score / count as f32),
+        }
+    }
+
+    /// Reset the model to its untrained state.
+    fn reset(&mut self) {
+        self.rankers.clear();
+        self.ranker_weights.clear();
+        self.best_rankers.clear();
+        self.best_weights.clear();
+        self.best_sample_weights.clear();
+        self.best_iteration = None;
+        self.used_features.clear();
+        self.consecutive_selections = 0;
+        self.previous_feature = usize::MAX;
+        self.previous_traning_score = 0.0;
+        self.previous_validation_score = 0.0;
+        self.score_validation = 0.0;
+        self.stale_iterations = 0;
+        self.sample_weights = self.uniform_weights();
+        self.history = self.new_history();
+    }
+
+    fn new_history(&self) -> TrainingHistory {
+        TrainingHistory::new(
+            &self.scorer.to_string(),
+            &self.validation_scorer().to_string(),
+        )
+    }
+
+    /// Continue the training of an already trained (or loaded) model.
+    ///
+    /// The weak rankers already in the model are kept and `iterations` more boosting
+    /// iterations are performed on the current training dataset, which can be replaced
+    /// with `set_train_dataset` beforehand. The best model is selected among the current
+    /// one and the ones found during the new iterations.
+    ///
+    /// # Arguments
+    /// * `iterations` - The number of additional iterations.
+    /// * `sample_weights` - How the sample weights distribution is initialized.
+    ///
+    /// # Errors
+    /// * `LtrError::NoRankers` if the model has no weak rankers.
+    /// * `LtrError::ConfigurationError` if the sample weights can't be restored.
+    pub fn warm_start(
+        &mut self,
+        iterations: u64,
+        sample_weights: SampleWeightsInit,
+    ) -> Result<(), LtrError> {
+        if self.rankers.is_empty() {
+            return Err(LtrError::NoRankers);
+        }
+
+        self.sample_weights = match sample_weights {
+            SampleWeightsInit::Restore => {
+                if self.sample_weights.len() != self.training_dataset.len() {
+                    return Err(LtrError::ConfigurationError(
+                        "The sample weights don't match the training dataset.",
+                    ));
+                }
+                std::mem::take(&mut self.sample_weights)
+            }
+            SampleWeightsInit::Recompute => self.recompute_weights()?,
+            SampleWeightsInit::Uniform => self.uniform_weights(),
+        };
+
+        // The current model is the baseline for the new iterations.
+        self.previous_traning_score = self.evaluate_training_dataset()?;
+
+        self.best_rankers.clear();
+        self.best_weights.clear();
+        self.best_sample_weights.clear();
+        self.best_iteration = None;
+        self.score_validation = 0.0;
+        self.previous_validation_score = 0.0;
+        self.stale_iterations = 0;
+        self.history = self.new_history();
+        if let Some(dataset) = &self.validation_dataset {
+            if !dataset.is_empty() {
+                self.score_validation = self.validation_scorer().evaluate_ranker_with(
+                    self,
+                    dataset,
+                    &self.ranking_policy,
+                )?;
+                self.previous_validation_score = self.score_validation;
+                self.best_rankers = self.rankers.clone();
+                self.best_weights = self.ranker_weights.clone();
+                self.best_sample_weights = self.sample_weights.clone();
+                self.best_iteration = Some(self.rankers.len() - 1);
+            }
+        }
+
+        self.train(iterations)
+    }
+
+    /// Write a checkpoint of the training state every `every` iterations.
+    ///
+    /// Each checkpoint overwrites the previous one and contains the ensemble, the sample
+    /// weights, the saturated features and the best model found so far. A last checkpoint
+    /// is written when the training is stopped by early stopping or by a callback.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the checkpoint file.
+    /// * `every` - The checkpoint frequency, in iterations. Must be greater than 0.
+    pub fn set_checkpoint(&mut self, path: &str, every: usize) {
+        self.checkpoint = Some((path.to_string(), every.max(1)));
+    }
+
+    /// Resume a training process from a checkpoint written during `fit` or `warm_start`.
+    ///
+    /// The `AdaRank` instance must be configured with the same datasets and parameters
+    /// as the interrupted one. The remaining iterations of the interrupted training are
+    /// performed: none if it was stopped by early stopping or by a callback.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the checkpoint file.
+    ///
+    /// # Errors
+    /// * `LtrError::ConfigurationError` if the checkpoint doesn't match the training dataset
+    ///   or was written with different parameters or metrics.
+    /// * `LtrError` if the checkpoint could not be read.
+    pub fn resume_from_checkpoint(&mut self, path: &str) -> Result<(), LtrError> {
+        let json = std::fs::read_to_string(path).map_err(|e| LtrError::IOError(e.to_string()))?;
+        let checkpoint: AdaRankCheckpoint =
+            serde_json::from_str(&json).map_err(|e| LtrError::SerializationError(e.to_string()))?;
+        if checkpoint.model.sample_weights.len() != self.training_dataset.len() {
+            return Err(LtrError::ConfigurationError(
+                "The checkpoint doesn't match the training dataset.",
+            ));
+        }
+        if checkpoint.params != self.params() {
+            return Err(LtrError::ConfigurationError(
+                "The checkpoint was written with different parameters.",
+            ));
+        }
+
+        self.rankers = checkpoint.model.rankers;
+        self.ranker_weights = checkpoint.model.ranker_weights;
+        self.sample_weights = checkpoint.model.sample_weights;
+        if let Some(names) = checkpoint.model.feature_names {
+            self.set_feature_names(names);
+        }
+        self.normalizer = checkpoint.model.normalizer;
+        self.best_rankers = checkpoint.best_rankers;
+        self.best_weights = checkpoint.best_weights;
+        self.best_sample_weights = checkpoint.best_sample_weights;
+        self.best_iteration = checkpoint.best_iteration;
+        self.score_validation = checkpoint.best_validation_score;
+        self.used_features = checkpoint.used_features;
+        self.consecutive_selections = checkpoint.consecutive_selections;
+        self.previous_feature = checkpoint.previous_feature;
+        self.previous_traning_score = checkpoint.previous_training_score;
+        self.previous_validation_score = checkpoint.previous_validation_score;
+        self.stale_iterations = checkpoint.stale_iterations;
+        self.history = checkpoint.history;
+
+        self.train(checkpoint.remaining_iterations)
+    }
+
+    /// Compute the weighted training score of each candidate feature.
+    /// With the `parallel` feature, the candidates are evaluated across CPU cores.
+    fn candidate_scores(&self, index: &FeatureIndex, candidates: &[usize]) -> Vec<f32> {
+        let sample_weights = &self.sample_weights;
+        let weighted_score = |feature: &usize| {
+            index
+                .weighted_score(*feature, sample_weights)
+                .unwrap_or(-1.0)
+        };
+
+        #[cfg(feature = "parallel")]
+        let scores = candidates.par_iter().map(weighted_score).collect();
+        #[cfg(not(feature = "parallel"))]
+        let scores = candidates.iter().map(weighted_score).collect();
+
+        scores
+    }
+
+    fn select_weak_ranker(&mut self, index: &FeatureIndex) -> Option<WeakRanker> {
         let mut best_score = -1.0;
         let mut best_feature = 0;
 
-        for feature in self.features.iter() {
-            if self.used_features.contains(feature) {
-                continue;
-            }
-            let ranker = WeakRanker::new(*feature);
-            let score = self.evaluate_weak_ranker(&ranker);
+        let candidates: Vec<usize> = self
+            .features
+            .iter()
+            .filter(|feature| !self.used_features.contains(feature))
+            .copied()
+            .collect();
+        let scores = self.candidate_scores(index, &candidates);
+
+        // Ties are resolved by the order of the features, regardless of how they were evaluated.
+        for (feature, score) in candidates.iter().zip(scores) {
             if score > best_score {
                 best_score = score;
                 best_feature = *feature;
@@ -227,10 +799,97 @@ impl AdaRank {
         Some(WeakRanker::new(best_feature))
     }
 
-    fn learn(&mut self) {
-        for it in 0..self.iter {
+    /// Run `iterations` boosting iterations starting from the current state
+    /// and keep the best model found.
+    fn train(&mut self, iterations: u64) -> Result<(), LtrError> {
+        tracing::debug!("{}", self.debug_header());
+
+        for callback in self.callbacks.iter_mut() {
+            callback.on_fit_start();
+        }
+
+        self.learn(iterations)?;
+
+        if !self.best_rankers.is_empty() {
+            self.rankers = std::mem::take(&mut self.best_rankers);
+            self.ranker_weights = std::mem::take(&mut self.best_weights);
+            if !self.best_sample_weights.is_empty() {
+                self.sample_weights = std::mem::take(&mut self.best_sample_weights);
+            }
+        } else {
+            self.best_iteration = self.rankers.len().checked_sub(1);
+        }
+        self.history.best_iteration = self.best_iteration;
+
+        for callback in self.callbacks.iter_mut() {
+            callback.on_fit_end(&self.history);
+        }
+
+        if self.rankers.is_empty() {
+            return Err(LtrError::NoRankers);
+        }
+
+        self.score_training = self.evaluate_training_dataset()?;
+
+        match &self.validation_dataset {
+            Some(dataset) => {
+                self.score_validation = self
+                    .validation_scorer()
+                    .evaluate_ranker_with(self, dataset, &self.ranking_policy)
+                    .unwrap_or_else(|e| {
+                        tracing::error!("Error evaluating validation dataset: {}", e);
+                        0.0
+                    });
+            }
+            None => {
+                self.score_validation = 0.0;
+            }
+        }
+
+        self.log_results();
+        Ok(())
+    }
+
+    /// Notify the callbacks about the last recorded iteration.
+    fn notify_iteration(&mut self, ranker: &WeakRanker) -> CallbackControl {
+        let record = match self.history.last() {
+            Some(record) => record,
+            None => return CallbackControl::Continue,
+        };
+        let context = IterationContext {
+            record,
+            ranker,
+            feature: ranker.feature_id,
+            ranker_weights: &self.ranker_weights,
+            sample_weights: &self.sample_weights,
+        };
+
+        let mut control = CallbackControl::Continue;
+        for callback in self.callbacks.iter_mut() {
+            if callback.on_iteration(&context) == CallbackControl::Stop {
+                control = CallbackControl::Stop;
+            }
+        }
+        control
+    }
+
+    fn learn(&mut self, iterations: u64) -> Result<(), LtrError> {
+        // The weak rankers metric values are computed once for the whole training.
+        let index = FeatureIndex::new(
+            &self.training_dataset,
+            &self.features,
+            &*self.scorer,
+            &self.ranking_policy,
+        )?;
+
+        let weighted = self.weighted_queries();
+        let num_weighted = weighted.iter().filter(|&&w| w).count();
+
+        // Iterations are numbered after the weak rankers already in the model.
+        let first_iteration = self.rankers.len();
+        for it in first_iteration..first_iteration + iterations as usize {
             // 1st step: select a weak ranker
-            let best_weak_ranker = match self.select_weak_ranker() {
+            let best_weak_ranker = match self.select_weak_ranker(&index) {
                 Some(ranker) => ranker,
                 None => {
                     tracing::error!("No weak ranker selected");
@@ -241,13 +900,10 @@ impl AdaRank {
             // 2nd step: evaluate the weak ranker (amount to say)
             let mut num = 0.0f32;
             let mut denom = 0.0f32;
-            for (ranklist, weight) in self
-                .training_dataset
-                .iter_mut()
-                .zip(self.sample_weights.iter())
-            {
-                best_weak_ranker.rank(ranklist);
-                let score = self.scorer.evaluate_ranklist(ranklist);
+            let weak_scores = index
+                .metrics(best_weak_ranker.feature_id)
+                .unwrap_or_default();
+            for (score, weight) in weak_scores.iter().zip(self.sample_weights.iter()) {
                 num += (1.0 + score) * *weight;
                 denom += (1.0 - score) * *weight;
             }
@@ -263,32 +919,35 @@ impl AdaRank {
             let mut training_score = 0.0f32;
             let mut total_score = 0.0f32;
 
-            let mut train_scores_list = Vec::new();
-            train_scores_list.reserve(self.training_dataset.len());
-
-            for ranklist in self.training_dataset.iter() {
-                self.rank(ranklist);
+            let mut train_scores_list = Vec::with_capacity(self.training_dataset.len());
 
-                let score = self.scorer.evaluate_ranklist(ranklist);
+            for (ranklist, &weighted) in self.training_dataset.iter().zip(weighted.iter()) {
+                let score = self.evaluate_ranklist(ranklist)?;
                 let exp_score = (-score).exp();
 
-                training_score += score;
-                total_score += exp_score;
+                if weighted {
+                    training_score += score;
+                    total_score += exp_score;
+                }
 
                 train_scores_list.push(exp_score);
             }
 
-            training_score /= self.training_dataset.len() as f32;
+            training_score /= num_weighted as f32;
             let delta = training_score + self.tolerance - self.previous_traning_score;
 
-            let mut status = if delta > 0.0 { "OK" } else { "BAD" };
+            let mut status = if delta > 0.0 {
+                IterationStatus::Ok
+            } else {
+                IterationStatus::Bad
+            };
 
             let selected_feature = best_weak_ranker.feature_id;
 
             if self.previous_feature == selected_feature {
                 self.consecutive_selections += 1;
                 if self.consecutive_selections == self.max_consecutive_selections {
-                    status = "SATURED";
+                    status = IterationStatus::Saturated;
                     self.consecutive_selections = 0;
                     self.used_features.insert(selected_feature);
                 }
@@ -296,106 +955,186 @@ impl AdaRank {
 
             self.previous_feature = selected_feature;
 
-            let mut val_score = 0.0f32;
+            let mut val_score = None;
             if let Some(val_dataset) = &self.validation_dataset {
-                if !val_dataset.is_empty() && it % 1 == 0 {
-                    self.rank_dataset(val_dataset);
-                    val_score = match self.scorer.evaluate_dataset(val_dataset) {
+                if !val_dataset.is_empty() {
+                    let score = match self.validation_scorer().evaluate_ranker_with(
+                        self,
+                        val_dataset,
+                        &self.ranking_policy,
+                    ) {
                         Ok(score) => score,
                         Err(e) => {
                             tracing::error!("Error evaluating validation dataset: {}", e);
                             0.0
                         }
                     };
-                    if val_score > self.score_validation {
-                        self.score_validation = val_score;
+                    val_score = Some(score);
+                    if score > self.score_validation + self.min_delta {
+                        self.score_validation = score;
                         self.best_rankers = self.rankers.clone();
                         self.best_weights = self.ranker_weights.clone();
+                        self.best_iteration = Some(it);
+                        self.stale_iterations = 0;
+                    } else {
+                        self.stale_iterations += 1;
                     }
                 }
             }
 
-            let train_improvement = training_score - self.previous_traning_score;
-            let validation_improvement = val_score - self.previous_validation_score;
+            let early_stop = self
+                .patience
+                .is_some_and(|patience| self.stale_iterations >= patience);
+            // A saturated feature explains the stop as well: the status is kept.
+            if early_stop && delta > 0.0 && status != IterationStatus::Saturated {
+                status = IterationStatus::Stop;
+            }
 
-            tracing::debug!(
-                "{}",
-                self.debug_line(
-                    it as usize,
-                    selected_feature,
-                    training_score,
-                    train_improvement,
-                    val_score,
-                    validation_improvement,
-                    status,
-                )
-            );
+            let record = IterationRecord {
+                iteration: it,
+                feature: selected_feature,
+                weight: amount_to_say,
+                training_score,
+                training_improvement: training_score - self.previous_traning_score,
+                validation_score: val_score,
+                validation_improvement: val_score.map(|v| v - self.previous_validation_score),
+                status,
+            };
+            tracing::debug!("{}", self.debug_line(&record));
+            self.history.push(record);
 
             if delta <= 0.0 {
+                // The callbacks see the model the record describes, before the weak
+                // ranker of the iteration is dropped.
+                self.notify_iteration(&best_weak_ranker);
+                if self.best_iteration == Some(it) {
+                    self.best_sample_weights = self.sample_weights.clone();
+                }
                 self.rankers.pop();
                 self.ranker_weights.pop();
                 break;
             }
 
             self.previous_traning_score = training_score;
-            self.previous_validation_score = val_score;
+            self.previous_validation_score = val_score.unwrap_or(0.0);
 
             // 5th step: update the weights distribution
             for (weight, score) in self.sample_weights.iter_mut().zip(train_scores_list.iter()) {
                 *weight *= (-amount_to_say * score).exp() / total_score;
             }
-        }
-    }
-}
+            if self.best_iteration == Some(it) {
+                // The best model goes on from the weights updated by its last weak ranker.
+                self.best_sample_weights = self.sample_weights.clone();
+            }
 
-impl Learner for AdaRank {
-    fn fit(&mut self) -> Result<(), crate::error::LtrError> {
-        tracing::debug!("{}", self.debug_header());
+            let mut stop = early_stop;
+            if self.notify_iteration(&best_weak_ranker) == CallbackControl::Stop {
+                tracing::debug!("Training stopped by a callback");
+                if let Some(record) = self.history.records.last_mut() {
+                    record.status = IterationStatus::Stop;
+                }
+                stop = true;
+            } else if early_stop {
+                tracing::debug!(
+                    "Early stopping: no validation improvement in the last {} iterations",
+                    self.stale_iterations
+                );
+            }
 
-        self.learn();
+            // A stopped training is checkpointed with no iterations left, so resuming it
+            // gives the same model.
+            let completed_iterations = it + 1 - first_iteration;
+            if let Some((path, every)) = &self.checkpoint {
+                if stop || completed_iterations.is_multiple_of(*every) {
+                    let remaining_iterations = match stop {
+                        true => 0,
+                        false => iterations - completed_iterations as u64,
+                    };
+                    self.save_checkpoint(path, remaining_iterations)?;
+                }
+            }
 
-        if !self.best_rankers.is_empty() {
-            self.rankers = std::mem::take(&mut self.best_rankers);
-            self.ranker_weights = std::mem::take(&mut self.best_weights);
+            if stop {
+                break;
+            }
         }
+        Ok(())
+    }
 
-        if self.rankers.is_empty() {
-            return Err(crate::error::LtrError::NoRankers);
+    /// Get the serializable state of the current model.
+    fn model(&self) -> AdaRankModel {
+        AdaRankModel {
+            rankers: self.rankers.clone(),
+            ranker_weights: self.ranker_weights.clone(),
+            sample_weights: self.sample_weights.clone(),
+            feature_names: self.feature_names.clone(),
+            normalizer: self.normalizer.clone(),
         }
+    }
 
-        self.rank_dataset(&self.training_dataset);
-        self.score_training = self.scorer.evaluate_dataset(&self.training_dataset)?;
-
-        match &self.validation_dataset {
-            Some(dataset) => {
-                self.rank_dataset(dataset);
-                self.score_validation = self
-                    .scorer
-                    .evaluate_dataset(&self.training_dataset)
-                    .unwrap_or_else(|e| {
-                        tracing::error!("Error evaluating training dataset: {}", e);
-                        0.0
-                    });
-            }
-            None => {
-                self.score_validation = 0.0;
-            }
+    /// Get the parameters a checkpoint must have been written with to be resumed.
+    fn params(&self) -> AdaRankParams {
+        AdaRankParams {
+            metric: self.scorer.to_string(),
+            validation_metric: self.validation_scorer().to_string(),
+            max_consecutive_selections: self.max_consecutive_selections,
+            tolerance: self.tolerance,
+            features: self.features.clone(),
+            patience: self.patience,
+            min_delta: self.min_delta,
+            ranking_policy: self.ranking_policy,
+            skip_queries_without_relevant: self.skip_queries_without_relevant,
         }
+    }
 
-        self.log_results();
+    /// Write the whole training state to `path`, so the training can be resumed
+    /// with `resume_from_checkpoint`.
+    fn save_checkpoint(&self, path: &str, remaining_iterations: u64) -> Result<(), LtrError> {
+        let checkpoint = AdaRankCheckpoint {
+            model: self.model(),
+            best_rankers: self.best_rankers.clone(),
+            best_weights: self.best_weights.clone(),
+            best_sample_weights: self.best_sample_weights.clone(),
+            best_iteration: self.best_iteration,
+            best_validation_score: self.score_validation,
+            used_features: self.used_features.clone(),
+            consecutive_selections: self.consecutive_selections,
+            previous_feature: self.previous_feature,
+            previous_training_score: self.previous_traning_score,
+            previous_validation_score: self.previous_validation_score,
+            stale_iterations: self.stale_iterations,
+            remaining_iterations,
+            history: self.history.clone(),
+            params: self.params(),
+        };
+        let json = serde_json::to_string(&checkpoint)
+            .map_err(|e| LtrError::SerializationError(e.to_string()))?;
+
+        // Write to a temporary file first, so a crash never leaves a truncated checkpoint.
+        let tmp_path = format!("{}.tmp", path);
+        std::fs::write(&tmp_path, json).map_err(|e| LtrError::IOError(e.to_string()))?;
+        std::fs::rename(&tmp_path, path).map_err(|e| LtrError::IOError(e.to_string()))?;
+        tracing::debug!("Checkpoint saved to {}", path);
         Ok(())
     }
+}
+
+impl Learner for AdaRank {
+    fn fit(&mut self) -> Result<(), LtrError> {
+        self.reset();
+        self.train(self.iter)
+    }
 
-    fn score(&self) -> Result<f32, crate::error::LtrError> {
+    fn score(&self) -> Result<f32, LtrError> {
         if self.rankers.is_empty() {
-            return Err(crate::error::LtrError::NoRankers);
+            return Err(LtrError::NoRankers);
         }
         Ok(self.score_training)
     }
 
-    fn validation_score(&self) -> Result<f32, crate::error::LtrError> {
+    fn validation_score(&self) -> Result<f32, LtrError> {
         if self.rankers.is_empty() {
-            return Err(crate::error::LtrError::NoRankers);
+            return Err(LtrError::NoRankers);
         }
         Ok(self.score_validation)
     }
@@ -405,19 +1144,59 @@ impl Ranker for AdaRank {
     fn predict(&self, datapoint: &crate::datapoint::DataPoint) -> f32 {
         let mut score = 0.0;
         for (ranker, weight) in self.rankers.iter().zip(self.ranker_weights.iter()) {
-            let feature_value: f32 = match datapoint.get_feature(ranker.feature_id) {
-                Ok(value) => *value,
-                Err(e) => {
-                    tracing::error!("Error getting feature value: {}", e);
-                    0.0
-                }
-            };
-            score += feature_value * weight;
+            // Missing features are zero, as in sparse rows.
+            let feature_value = datapoint
+                .get_feature(ranker.feature_id)
+                .copied()
+                .unwrap_or(0.0);
+            score += self.normalize(ranker.feature_id, feature_value) * weight;
+        }
+        score
+    }
+
+    fn predict_features(&self, features: &[f32]) -> f32 {
+        let mut score = 0.0;
+        for (ranker, weight) in self.rankers.iter().zip(self.ranker_weights.iter()) {
+            let feature_value = ranker.predict_features(features);
+            score += self.normalize(ranker.feature_id, feature_value) * weight;
         }
         score
     }
 }
 
+impl FileSerializable for AdaRank {
+    fn to_json(&self) -> Result<String, LtrError> {
+        serde_json::to_string_pretty(&self.model())
+            .map_err(|e| LtrError::SerializationError(e.to_string()))
+    }
+
+    fn load_json(&mut self, json: &str) -> Result<(), LtrError> {
+        let model: AdaRankModel =
+            serde_json::from_str(json).map_err(|e| LtrError::SerializationError(e.to_string()))?;
+        if model.rankers.len() != model.ranker_weights.len() {
+            return Err(LtrError::SerializationError(
+                "the number of rankers and weights differ".to_string(),
+            ));
+        }
+
+        self.reset();
+        self.rankers = model.rankers;
+        self.ranker_weights = model.ranker_weights;
+        self.sample_weights = model.sample_weights;
+        if let Some(names) = model.feature_names {
+            self.set_feature_names(names);
+        }
+        self.normalizer = model.normalizer;
+        Ok(())
+    }
+}
+
+impl CallbackConfigurable for AdaRank {
+    fn add_callback(&mut self, callback: Box<dyn TrainingCallback>) {
+        self.callbacks.push(callback);
+    }
+}
+
 impl FeaturesConfigurable for AdaRank {
     fn set_features(&mut self, features: Vec<usize>) {
         self.features = features;
@@ -432,10 +1211,512 @@ impl MetricConfigurable for AdaRank {
 
 impl DatasetConfigurable for AdaRank {
     fn set_train_dataset(&mut self, dataset: DataSet) {
-        self.training_dataset = dataset;
+        AdaRank::set_train_dataset(self, dataset);
     }
 
     fn set_validation_dataset(&mut self, dataset: DataSet) {
-        self.validation_dataset = Some(dataset);
+        AdaRank::set_validation_dataset(self, dataset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        datapoint::{DataPoint, FeatureStorage},
+        eval::precision::Precision,
+        eval::{
+            map::MAP,
+            no_relevant::{NoRelevantEvaluator, NoRelevantPolicy},
+        },
+    };
+    use crate::{
+        ranker::{NanPolicy, TieBreaking},
+        rl,
+    };
+
+    use approx::relative_eq;
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+    };
+
+    fn toy_dataset() -> DataSet {
+        DataSet::from(vec![
+            rl!(
+                (1, 1, vec![0.9, 0.1, 0.5]),
+                (0, 1, vec![0.2, 0.8, 0.4]),
+                (0, 1, vec![0.1, 0.3, 0.6])
+            ),
+            rl!(
+                (0, 2, vec![0.3, 0.9, 0.2]),
+                (1, 2, vec![0.8, 0.2, 0.1]),
+                (0, 2, vec![0.4, 0.5, 0.7])
+            ),
+            rl!(
+                (0, 3, vec![0.9, 0.4, 0.3]),
+                (0, 3, vec![0.2, 0.6, 0.5]),
+                (1, 3, vec![0.7, 0.7, 0.8])
+            ),
+        ])
+    }
+
+    struct RecordSampleWeights {
+        sample_weights: Rc<RefCell<Vec<Vec<f32>>>>,
+    }
+
+    impl TrainingCallback for RecordSampleWeights {
+        fn on_iteration(&mut self, context: &IterationContext) -> CallbackControl {
+            let sample_weights = context.sample_weights.to_vec();
+            self.sample_weights.borrow_mut().push(sample_weights);
+            CallbackControl::Continue
+        }
+    }
+
+    #[test]
+    fn test_early_stopping() {
+        let mut adarank = AdaRank::new(
+            toy_dataset(),
+            Box::new(MAP),
+            50,
+            100,
+            1.0,
+            None,
+            Some(toy_dataset()),
+        );
+        adarank.set_early_stopping(2, 0.0);
+        adarank.set_validation_metric(Box::new(Precision::new(1)));
+        let sample_weights = Rc::new(RefCell::new(Vec::new()));
+        adarank.add_callback(Box::new(RecordSampleWeights {
+            sample_weights: sample_weights.clone(),
+        }));
+
+        adarank.fit().unwrap();
+
+        assert_eq!(adarank.best_iteration(), Some(0));
+        // The sample weights are restored along with the best model.
+        assert_eq!(sample_weights.borrow().len(), 3);
+        assert_eq!(adarank.sample_weights, sample_weights.borrow()[0]);
+        assert_ne!(adarank.sample_weights, sample_weights.borrow()[2]);
+        assert_eq!(adarank.history().len(), 3);
+        assert_eq!(adarank.history().best_iteration, Some(0));
+        assert_eq!(
+            adarank.history().last().unwrap().status,
+            IterationStatus::Stop
+        );
+        assert_eq!(adarank.rankers.len(), 1);
+        assert_eq!(adarank.rankers[0].feature_id, 1);
+        assert!(relative_eq!(
+            adarank.validation_score().unwrap(),
+            0.666,
+            max_relative = 0.01f32
+        ));
+    }
+
+    #[test]
+    fn test_early_stopping_saturated() {
+        // Early stopping after a saturated feature keeps the `Saturated` status.
+        let mut adarank = AdaRank::new(
+            toy_dataset(),
+            Box::new(MAP),
+            20,
+            2,
+            1.0,
+            None,
+            Some(toy_dataset()),
+        );
+        adarank.set_early_stopping(2, 0.0);
+        adarank.fit().unwrap();
+
+        let statuses: Vec<IterationStatus> = adarank
+            .history()
+            .records
+            .iter()
+            .map(|record| record.status)
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                IterationStatus::Ok,
+                IterationStatus::Ok,
+                IterationStatus::Saturated
+            ]
+        );
+    }
+
+    struct StopAfter {
+        iterations: usize,
+        calls: Rc<Cell<(usize, usize, usize)>>,
+    }
+
+    impl TrainingCallback for StopAfter {
+        fn on_fit_start(&mut self) {
+            let (start, it, end) = self.calls.get();
+            self.calls.set((start + 1, it, end));
+        }
+
+        fn on_iteration(&mut self, context: &IterationContext) -> CallbackControl {
+            let (start, it, end) = self.calls.get();
+            self.calls.set((start, it + 1, end));
+            assert_eq!(context.record.feature, context.feature);
+            let mut features = vec![0.0; 3];
+            features[context.feature - 1] = 0.5;
+            assert_eq!(context.ranker.predict_features(&features), 0.5);
+            assert_eq!(context.ranker_weights.len(), context.record.iteration + 1);
+            assert_eq!(context.ranker_weights.last(), Some(&context.record.weight));
+            if context.record.iteration + 1 >= self.iterations {
+                CallbackControl::Stop
+            } else {
+                CallbackControl::Continue
+            }
+        }
+
+        fn on_fit_end(&mut self, history: &TrainingHistory) {
+            let (start, it, end) = self.calls.get();
+            self.calls.set((start, it, end + history.len()));
+        }
+    }
+
+    #[test]
+    fn test_callbacks() {
+        let calls = Rc::new(Cell::new((0, 0, 0)));
+        let mut adarank = AdaRank::new(toy_dataset(), Box::new(MAP), 50, 100, 1.0, None, None);
+        adarank.add_callback(Box::new(StopAfter {
+            iterations: 2,
+            calls: calls.clone(),
+        }));
+
+        adarank.fit().unwrap();
+
+        assert_eq!(calls.get(), (1, 2, 2));
+        assert_eq!(adarank.rankers.len(), 2);
+        assert_eq!(
+            adarank.history().last().unwrap().status,
+            IterationStatus::Stop
+        );
+
+        // A negative tolerance makes the first iteration BAD: its weak ranker is
+        // still in the model seen by the callbacks.
+        let calls = Rc::new(Cell::new((0, 0, 0)));
+        let mut adarank = AdaRank::new(toy_dataset(), Box::new(MAP), 50, 100, -1.0, None, None);
+        adarank.add_callback(Box::new(StopAfter {
+            iterations: 50,
+            calls: calls.clone(),
+        }));
+        assert_eq!(adarank.fit(), Err(LtrError::NoRankers));
+        assert_eq!(calls.get(), (1, 1, 1));
+        assert_eq!(
+            adarank.history().last().unwrap().status,
+            IterationStatus::Bad
+        );
+    }
+
+    #[test]
+    fn test_warm_start() {
+        let mut adarank = AdaRank::new(toy_dataset(), Box::new(MAP), 1, 100, 1.0, None, None);
+        assert_eq!(
+            adarank.warm_start(1, SampleWeightsInit::Uniform),
+            Err(LtrError::NoRankers)
+        );
+
+        adarank.fit().unwrap();
+        assert_eq!(adarank.rankers.len(), 1);
+
+        let path = std::env::temp_dir().join("adarank_test_warm_start.json");
+        let path = path.to_str().unwrap();
+        adarank.save_to_file(path).unwrap();
+
+        let mut loaded = AdaRank::new(toy_dataset(), Box::new(MAP), 1, 100, 1.0, None, None);
+        loaded.load_from_file(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(loaded.rankers, adarank.rankers);
+        assert_eq!(loaded.sample_weights, adarank.sample_weights);
+
+        loaded.warm_start(2, SampleWeightsInit::Restore).unwrap();
+        assert_eq!(loaded.rankers.len(), 3);
+        assert_eq!(loaded.rankers[0], adarank.rankers[0]);
+        assert_eq!(loaded.history().records[0].iteration, 1);
+        assert_eq!(loaded.best_iteration(), Some(2));
+
+        loaded.set_train_dataset(toy_dataset()[..2].to_vec());
+        assert!(loaded.warm_start(1, SampleWeightsInit::Restore).is_err());
+        loaded.warm_start(1, SampleWeightsInit::Recompute).unwrap();
+        assert_eq!(loaded.rankers.len(), 4);
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        let mut reference = AdaRank::new(toy_dataset(), Box::new(MAP), 3, 100, 1.0, None, None);
+        reference.fit().unwrap();
+
+        let path = std::env::temp_dir().join("adarank_test_checkpoint.json");
+        let path = path.to_str().unwrap();
+
+        // The last checkpoint is written after two iterations, as if the training had
+        // crashed during the third one.
+        let mut interrupted = AdaRank::new(toy_dataset(), Box::new(MAP), 3, 100, 1.0, None, None);
+        interrupted.set_checkpoint(path, 2);
+        interrupted.fit().unwrap();
+
+        let mut resumed = AdaRank::new(toy_dataset(), Box::new(MAP), 3, 100, 1.0, None, None);
+        resumed.resume_from_checkpoint(path).unwrap();
+
+        assert_eq!(resumed.rankers, reference.rankers);
+        assert_eq!(resumed.ranker_weights, reference.ranker_weights);
+        assert_eq!(resumed.history().len(), 3);
+        assert_eq!(resumed.best_iteration(), Some(2));
+
+        // The parameters must match the checkpoint.
+        let mut other = AdaRank::new(toy_dataset(), Box::new(MAP), 3, 100, 0.5, None, None);
+        assert!(matches!(
+            other.resume_from_checkpoint(path),
+            Err(LtrError::ConfigurationError(_))
+        ));
+        let mut other = AdaRank::new(
+            toy_dataset(),
+            Box::new(Precision::new(1)),
+            3,
+            100,
+            1.0,
+            None,
+            None,
+        );
+        assert!(matches!(
+            other.resume_from_checkpoint(path),
+            Err(LtrError::ConfigurationError(_))
+        ));
+        std::fs::remove_file(path).unwrap();
+
+        // A training stopped by a callback resumes without any new iteration.
+        let calls = Rc::new(Cell::new((0, 0, 0)));
+        let mut stopped = AdaRank::new(toy_dataset(), Box::new(MAP), 3, 100, 1.0, None, None);
+        stopped.set_checkpoint(path, 5);
+        stopped.add_callback(Box::new(StopAfter {
+            iterations: 2,
+            calls: calls.clone(),
+        }));
+        stopped.fit().unwrap();
+
+        let mut resumed = AdaRank::new(toy_dataset(), Box::new(MAP), 3, 100, 1.0, None, None);
+        resumed.resume_from_checkpoint(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(resumed.rankers, stopped.rankers);
+        assert_eq!(resumed.ranker_weights, stopped.ranker_weights);
+        assert_eq!(resumed.history().len(), 2);
+        assert_eq!(
+            resumed.history().last().unwrap().status,
+            IterationStatus::Stop
+        );
+    }
+
+    #[test]
+    fn test_candidate_scores() {
+        let adarank = AdaRank::new(toy_dataset(), Box::new(MAP), 3, 100, 1.0, None, None);
+        let index = FeatureIndex::new(
+            &adarank.training_dataset,
+            &adarank.features,
+            &MAP,
+            &RankingPolicy::default(),
+        )
+        .unwrap();
+        let candidates = vec![1, 2, 3];
+
+        // Evaluated in parallel when the `parallel` feature is enabled.
+        let scores = adarank.candidate_scores(&index, &candidates);
+
+        let serial: Vec<f32> = candidates
+            .iter()
+            .map(|feature| {
+                let ranker = WeakRanker::new(*feature);
+                let mut score = 0.0;
+                for (ranklist, weight) in adarank
+                    .training_dataset
+                    .iter()
+                    .zip(adarank.sample_weights.iter())
+                {
+                    let ranklist = ranklist.clone();
+                    ranker.rank(&ranklist);
+                    score += MAP.evaluate_ranklist(&ranklist) * weight;
+                }
+                score
+            })
+            .collect();
+        assert_eq!(scores, serial);
+    }
+
+    #[test]
+    fn test_ranking_policy() {
+        let mut dataset = toy_dataset();
+        dataset.push(rl!(
+            (1, 4, vec![f32::NAN, 0.3, 0.2]),
+            (0, 4, vec![0.5, 0.3, 0.9])
+        ));
+
+        let mut adarank = AdaRank::new(dataset.clone(), Box::new(MAP), 3, 100, 1.0, None, None);
+        adarank.fit().unwrap();
+        assert!(adarank.score_training.is_finite());
+
+        let mut adarank = AdaRank::new(dataset, Box::new(MAP), 3, 100, 1.0, None, None);
+        adarank.set_ranking_policy(RankingPolicy::new(NanPolicy::Error, TieBreaking::Average));
+        assert_eq!(adarank.fit(), Err(LtrError::NanScore(0)));
+    }
+
+    #[test]
+    fn test_skip_queries_without_relevant() {
+        let mut dataset = toy_dataset();
+        dataset.push(rl!(
+            (0, 4, vec![0.5, 0.3, 0.2]),
+            (0, 4, vec![0.1, 0.3, 0.9])
+        ));
+
+        let mut adarank = AdaRank::new(dataset.clone(), Box::new(MAP), 3, 100, 1.0, None, None);
+        adarank.set_skip_queries_without_relevant(true);
+        assert_eq!(
+            adarank.sample_weights,
+            vec![1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0, 0.0]
+        );
+        adarank.fit().unwrap();
+        assert_eq!(adarank.sample_weights[3], 0.0);
+
+        // The training score ignores the skipped query, as the trained model on the
+        // dataset without it does.
+        let mut reference = AdaRank::new(toy_dataset(), Box::new(MAP), 3, 100, 1.0, None, None);
+        reference.fit().unwrap();
+        assert_eq!(adarank.rankers, reference.rankers);
+        assert_eq!(adarank.score_training, reference.score_training);
+        let skip = NoRelevantEvaluator::new(Box::new(MAP), NoRelevantPolicy::Skip);
+        assert_eq!(
+            skip.evaluate_ranker(&adarank, &dataset).unwrap(),
+            adarank.score_training
+        );
+    }
+
+    #[test]
+    fn test_predict_features() {
+        let mut adarank = AdaRank::new(toy_dataset(), Box::new(MAP), 3, 100, 1.0, None, None);
+        adarank.fit().unwrap();
+
+        let dataset = toy_dataset();
+        for ranklist in dataset.iter() {
+            for dp in ranklist.data_points().iter() {
+                assert_eq!(
+                    adarank.predict_features(&dp.features().to_dense()),
+                    adarank.predict(dp)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_sparse_training() {
+        let dense = toy_dataset();
+        let sparse = toy_dataset();
+        for ranklist in sparse.iter() {
+            for i in 0..ranklist.len() {
+                let mut dp = ranklist.get(i).unwrap().clone();
+                dp.set_feature_storage(FeatureStorage::Sparse);
+                ranklist.set(i, dp).unwrap();
+            }
+        }
+
+        let mut dense_model = AdaRank::new(dense, Box::new(MAP), 3, 100, 1.0, None, None);
+        let mut sparse_model = AdaRank::new(sparse, Box::new(MAP), 3, 100, 1.0, None, None);
+        dense_model.fit().unwrap();
+        sparse_model.fit().unwrap();
+
+        assert_eq!(dense_model.rankers, sparse_model.rankers);
+        assert_eq!(dense_model.ranker_weights, sparse_model.ranker_weights);
+    }
+
+    #[test]
+    fn test_feature_names() {
+        let mut dataset = toy_dataset();
+        dataset.set_feature_names(FeatureNames::parse("1 TF-title\n2 BM25-title").unwrap());
+
+        let mut adarank = AdaRank::new(dataset, Box::new(MAP), 3, 100, 1.0, None, None);
+        assert_eq!(adarank.feature_label(2), "BM25-title");
+        assert_eq!(adarank.feature_label(3), "3");
+        adarank.fit().unwrap();
+        assert!(adarank
+            .debug_line(&adarank.history().records[0])
+            .contains("title"));
+
+        let importance = adarank.feature_importance();
+        let total: f32 = adarank.ranker_weights.iter().sum();
+        assert!(relative_eq!(
+            importance.iter().map(|(_, weight)| weight).sum::<f32>(),
+            total,
+            max_relative = 0.0001f32
+        ));
+        assert!(importance.windows(2).all(|w| w[0].1 >= w[1].1));
+
+        let path = std::env::temp_dir().join("adarank_test_feature_names.json");
+        let path = path.to_str().unwrap();
+        adarank.save_to_file(path).unwrap();
+        assert!(std::fs::read_to_string(path)
+            .unwrap()
+            .contains("BM25-title"));
+
+        let mut loaded = AdaRank::new(toy_dataset(), Box::new(MAP), 1, 100, 1.0, None, None);
+        assert!(loaded.feature_names().is_none());
+        loaded.load_from_file(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(loaded.feature_names(), adarank.feature_names());
+        assert_eq!(loaded.feature_importance(), importance);
+    }
+
+    #[test]
+    fn test_normalizer() {
+        use crate::normalize::{NormalizationMethod, NormalizationScope};
+
+        let mut adarank = AdaRank::new(toy_dataset(), Box::new(MAP), 3, 100, 1.0, None, None);
+        let per_query = Normalizer::new(NormalizationMethod::Sum, NormalizationScope::PerQuery);
+        assert!(adarank.set_normalizer(per_query).is_err());
+
+        let mut zscore = Normalizer::new(NormalizationMethod::ZScore, NormalizationScope::Global);
+        assert!(adarank.set_normalizer(zscore.clone()).is_err());
+        zscore.fit(&toy_dataset());
+        adarank.set_normalizer(zscore.clone()).unwrap();
+        adarank.fit().unwrap();
+
+        // Raw features are normalized before being weighted.
+        let raw = vec![0.9, 0.1, 0.5];
+        let mut normalized = raw.clone();
+        zscore.transform_features(&mut normalized).unwrap();
+        let expected: f32 = adarank
+            .rankers
+            .iter()
+            .zip(adarank.ranker_weights.iter())
+            .map(|(ranker, weight)| normalized[ranker.feature_id - 1] * weight)
+            .sum();
+        assert_eq!(adarank.predict_features(&raw), expected);
+        assert_eq!(
+            adarank.predict(&DataPoint::new(0, 1, raw.clone(), None)),
+            expected
+        );
+
+        // A normalized row without the features of the model is scored as the model
+        // scores the raw one.
+        let short = DataPoint::new(0, 1, vec![], None);
+        let test = DataSet::from(vec![RankList::new(vec![short.clone()])]);
+        zscore.transform(&test).unwrap();
+        let normalized = test[0].get(0).unwrap().features().to_dense();
+        let expected_short: f32 = adarank
+            .rankers
+            .iter()
+            .zip(adarank.ranker_weights.iter())
+            .map(|(ranker, weight)| normalized[ranker.feature_id - 1] * weight)
+            .sum();
+        assert!(relative_eq!(adarank.predict(&short), expected_short));
+
+        let path = std::env::temp_dir().join("adarank_test_normalizer_model.json");
+        let path = path.to_str().unwrap();
+        adarank.save_to_file(path).unwrap();
+        let mut loaded = AdaRank::new(toy_dataset(), Box::new(MAP), 1, 100, 1.0, None, None);
+        loaded.load_from_file(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(loaded.normalizer(), Some(&zscore));
+        assert_eq!(loaded.predict_features(&raw), expected);
     }
 }
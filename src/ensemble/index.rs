@@ -0,0 +1,173 @@
+/// Copyright (c) 2021 Marcos Pontes
+/// MIT License
+///
+use std::cell::Ref;
+use std::collections::HashMap;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use super::weak::WeakRanker;
+
+use crate::{
+    datapoint::DataPoint,
+    error::LtrError,
+    eval::Evaluator,
+    ranker::{Ranker, RankingPolicy},
+    DataSet,
+};
+
+///
+/// A `FeatureIndex` is an indexed representation of a training `DataSet` for
+/// single-feature `WeakRanker`s.
+///
+/// The ranking produced by a `WeakRanker` never changes during the training, and neither
+/// does its metric value on each query. The index computes the metric values once, so a boosting
+/// iteration only needs to reweight the precomputed metric values.
+///
+#[derive(Debug, Clone)]
+pub struct FeatureIndex {
+    /// Row of each indexed feature.
+    rows: HashMap<usize, usize>,
+    /// Number of indexed queries.
+    num_queries: usize,
+    /// Per-feature metric value of every query.
+    metrics: Vec<Vec<f32>>,
+}
+
+impl FeatureIndex {
+    ///
+    /// Build the index of a `DataSet` for the given features.
+    /// With the `parallel` feature, the features are indexed across CPU cores.
+    ///
+    /// # Arguments
+    /// * `dataset` - The `DataSet` to be indexed.
+    /// * `features` - The features to be indexed.
+    /// * `scorer` - The metric computed for each feature and query.
+    /// * `policy` - How NaN values and ties are handled when ranking by a feature.
+    ///
+    /// # Errors
+    /// `LtrError::NanScore` if a feature value is NaN and the policy is `NanPolicy::Error`.
+    ///
+    pub fn new(
+        dataset: &DataSet,
+        features: &[usize],
+        scorer: &dyn Evaluator,
+        policy: &RankingPolicy,
+    ) -> Result<Self, LtrError> {
+        // The borrowed slices, unlike the `RankList`s, can be shared across threads.
+        let borrows: Vec<Ref<'_, [DataPoint]>> = dataset
+            .iter()
+            .map(|ranklist| ranklist.data_points())
+            .collect();
+        let samples: Vec<&[DataPoint]> = borrows.iter().map(|sample| &**sample).collect();
+
+        let index_feature = |feature: &usize| index_feature(scorer, &samples, *feature, policy);
+
+        #[cfg(feature = "parallel")]
+        let metrics: Result<Vec<Vec<f32>>, LtrError> =
+            features.par_iter().map(index_feature).collect();
+        #[cfg(not(feature = "parallel"))]
+        let metrics: Result<Vec<Vec<f32>>, LtrError> = features.iter().map(index_feature).collect();
+
+        Ok(FeatureIndex {
+            rows: features.iter().enumerate().map(|(i, f)| (*f, i)).collect(),
+            num_queries: samples.len(),
+            metrics: metrics?,
+        })
+    }
+
+    ///
+    /// Get the number of indexed queries.
+    ///
+    pub fn num_queries(&self) -> usize {
+        self.num_queries
+    }
+
+    ///
+    /// Get the metric value of each query when ranked by a feature.
+    ///
+    pub fn metrics(&self, feature: usize) -> Option<&[f32]> {
+        let row = *self.rows.get(&feature)?;
+        Some(&self.metrics[row])
+    }
+
+    ///
+    /// Get the metric value of a feature weighted by the queries weights.
+    ///
+    pub fn weighted_score(&self, feature: usize, weights: &[f32]) -> Option<f32> {
+        let metrics = self.metrics(feature)?;
+        Some(
+            metrics
+                .iter()
+                .zip(weights.iter())
+                .fold(0.0, |score, (metric, weight)| score + metric * weight),
+        )
+    }
+}
+
+/// Rank every query by a single feature.
+///
+/// The samples are ranked through index permutations, so they are never reordered.
+///
+/// # Returns
+/// The metric value of each query.
+fn index_feature(
+    scorer: &dyn Evaluator,
+    samples: &[&[DataPoint]],
+    feature: usize,
+    policy: &RankingPolicy,
+) -> Result<Vec<f32>, LtrError> {
+    let ranker = WeakRanker::new(feature);
+    let mut metrics = Vec::with_capacity(samples.len());
+
+    for sample in samples.iter() {
+        let ranking = ranker.scored_ranking_with(sample, policy)?;
+        metrics.push(scorer.evaluate_ranking_with(sample, &ranking, policy));
+    }
+
+    Ok(metrics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        eval::map::MAP,
+        ranker::{NanPolicy, TieBreaking},
+        ranklist::RankList,
+        rl,
+    };
+
+    #[test]
+    fn test_feature_index() {
+        let dataset = DataSet::from(vec![
+            rl!(
+                (0, 1, vec![0.1, 0.5]),
+                (1, 1, vec![0.9, 0.1]),
+                (0, 1, vec![0.5, 0.9])
+            ),
+            rl!((1, 2, vec![0.2, 0.3]), (0, 2, vec![0.4, 0.3])),
+        ]);
+
+        let index = FeatureIndex::new(&dataset, &[1, 2], &MAP, &RankingPolicy::default()).unwrap();
+
+        assert_eq!(index.num_queries(), 2);
+        assert_eq!(index.metrics(1), Some(&[1.0, 0.5][..]));
+        // Ties keep the original order.
+        assert_eq!(index.metrics(2), Some(&[1.0 / 3.0, 1.0][..]));
+        assert_eq!(index.metrics(3), None);
+        assert_eq!(index.weighted_score(1, &[0.5, 0.5]), Some(0.75));
+
+        // The dataset is never reordered.
+        assert_eq!(dataset[0].get(0).unwrap().get_label(), 0.0);
+
+        let worst_case = RankingPolicy::new(NanPolicy::Last, TieBreaking::WorstCase);
+        let index = FeatureIndex::new(&dataset, &[2], &MAP, &worst_case).unwrap();
+        assert_eq!(index.metrics(2), Some(&[1.0 / 3.0, 0.5][..]));
+
+        let average = RankingPolicy::new(NanPolicy::Last, TieBreaking::Average);
+        let index = FeatureIndex::new(&dataset, &[2], &MAP, &average).unwrap();
+        assert_eq!(index.metrics(2), Some(&[1.0 / 3.0, 0.75][..]));
+    }
+}
@@ -1,16 +1,23 @@
 /// Copyright (c) 2021 Marcos Pontes
 /// MIT License
-///
-
 ///
 /// This module  contains the implementation of weak rankers used on
-/// ensemble methods.   
+/// ensemble methods.
 ///
-
 /// Weak ranker definition as components for the ensemble methods.
 pub mod weak;
 
+///
+/// Indexed training representation for single-feature weak rankers.
+///
+pub mod index;
+
 ///
 /// This module  contains the implementation of the ensemble method AdaRank
 ///
 pub mod adarank;
+
+///
+/// Grid and random search over the hyperparameters of AdaRank.
+///
+pub mod search;
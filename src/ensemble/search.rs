@@ -0,0 +1,436 @@
+/// Copyright (c) 2021 Marcos Pontes
+// This code is licensed under MIT license (see LICENSE for details)
+use std::fmt;
+use std::sync::Arc;
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use super::adarank::AdaRank;
+use crate::{
+    cv::{cross_validate, KFold},
+    error::LtrError,
+    eval::{map::MAP, Evaluator},
+    learner::Learner,
+    utils::prettytable::{Alignment, Table, TableConfig},
+    DataSet,
+};
+
+/// Builds a fresh training metric for each candidate, since `Evaluator`s can't be cloned.
+pub type MetricFactory = Arc<dyn Fn() -> Box<dyn Evaluator> + Send + Sync>;
+
+/// The values tried for each `AdaRank` hyperparameter.
+#[derive(Clone)]
+pub struct SearchSpace {
+    /// Values of the number of iterations.
+    pub iter: Vec<u64>,
+    /// Values of the maximum number of consecutive selections of a feature.
+    pub max_consecutive_selections: Vec<usize>,
+    /// Values of the tolerance criteria.
+    pub tolerance: Vec<f32>,
+    /// Feature subsets. `None` uses all the features.
+    pub features: Vec<Option<Vec<usize>>>,
+    /// Training metrics.
+    metrics: Vec<MetricFactory>,
+}
+
+impl SearchSpace {
+    /// Creates a `SearchSpace` with a single value per hyperparameter: 50 iterations,
+    /// 5 consecutive selections, a tolerance of 0.002, all the features and MAP.
+    pub fn new() -> SearchSpace {
+        SearchSpace {
+            iter: vec![50],
+            max_consecutive_selections: vec![5],
+            tolerance: vec![0.002],
+            features: vec![None],
+            metrics: vec![Arc::new(|| Box::new(MAP))],
+        }
+    }
+
+    /// Set the training metrics to be tried.
+    ///
+    /// # Arguments
+    /// * `metrics` - Functions building each training metric, e.g. `Arc::new(|| Box::new(MAP))`.
+    pub fn set_metrics(&mut self, metrics: Vec<MetricFactory>) {
+        self.metrics = metrics;
+    }
+
+    /// Enumerate every combination of the hyperparameter values.
+    fn grid(&self) -> Vec<AdaRankParams> {
+        let mut grid = Vec::new();
+        for (metric, factory) in self.metrics.iter().enumerate() {
+            let metric_name = factory().to_string();
+            for &iter in self.iter.iter() {
+                for &max_consecutive_selections in self.max_consecutive_selections.iter() {
+                    for &tolerance in self.tolerance.iter() {
+                        for features in self.features.iter() {
+                            grid.push(AdaRankParams {
+                                iter,
+                                max_consecutive_selections,
+                                tolerance,
+                                features: features.clone(),
+                                metric: metric_name.clone(),
+                                metric_index: metric,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        grid
+    }
+}
+
+impl Default for SearchSpace {
+    fn default() -> Self {
+        SearchSpace::new()
+    }
+}
+
+/// A configuration of the `AdaRank` hyperparameters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdaRankParams {
+    /// The number of iterations.
+    pub iter: u64,
+    /// The maximum number of consecutive selections of a feature.
+    pub max_consecutive_selections: usize,
+    /// The tolerance criteria.
+    pub tolerance: f32,
+    /// The feature subset. `None` uses all the features.
+    pub features: Option<Vec<usize>>,
+    /// The name of the training metric.
+    pub metric: String,
+    /// Position of the training metric in the `SearchSpace`.
+    metric_index: usize,
+}
+
+impl AdaRankParams {
+    fn features_label(&self) -> String {
+        match &self.features {
+            Some(features) => features
+                .iter()
+                .map(|f| f.to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+            None => "all".to_string(),
+        }
+    }
+}
+
+/// How each candidate configuration is scored.
+#[derive(Clone)]
+pub enum Scoring {
+    /// Train on the training `DataSet` and score on this validation `DataSet`.
+    Validation(DataSet),
+    /// Average the held-out scores of a cross-validation on the training `DataSet`.
+    CrossValidation(KFold),
+}
+
+/// The score of a candidate configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CandidateScore {
+    /// The hyperparameters of the candidate.
+    pub params: AdaRankParams,
+    /// The validation or cross-validation score.
+    pub score: f32,
+}
+
+/// Results of a hyperparameter search, with one `CandidateScore` per candidate
+/// in the order they were tried.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchReport {
+    /// Name of the metric used to score the candidates.
+    pub metric: String,
+    /// Scores of the candidates.
+    pub candidates: Vec<CandidateScore>,
+}
+
+impl SearchReport {
+    /// Get the best candidate. Ties are won by the candidate tried first.
+    pub fn best(&self) -> Option<&CandidateScore> {
+        self.candidates
+            .iter()
+            .fold(None, |best, candidate| match best {
+                Some(best) if best.score >= candidate.score => Some(best),
+                _ => Some(candidate),
+            })
+    }
+}
+
+impl fmt::Display for SearchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let table = Table::new(TableConfig::new(
+            vec![6, 5, 9, 9, 12, 9, 9],
+            (2, 2),
+            Alignment::Center,
+        ));
+        let best = self.best();
+
+        writeln!(
+            f,
+            "{}",
+            table.render(
+                vec![
+                    "Iter",
+                    "Max-C",
+                    "Tolerance",
+                    "Metric-T",
+                    "Features",
+                    self.metric.as_str(),
+                    "Best",
+                ],
+                None,
+            )
+        )?;
+        for (i, candidate) in self.candidates.iter().enumerate() {
+            let params = &candidate.params;
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "{}",
+                table.render(
+                    vec![
+                        params.iter.to_string().as_str(),
+                        params.max_consecutive_selections.to_string().as_str(),
+                        params.tolerance.to_string().as_str(),
+                        params.metric.as_str(),
+                        params.features_label().as_str(),
+                        format!("{:.5}", candidate.score).as_str(),
+                        if best == Some(candidate) { "*" } else { "" },
+                    ],
+                    None,
+                )
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Grid and random search over the `AdaRank` hyperparameters.
+pub struct HyperparameterSearch {
+    /// The values tried for each hyperparameter.
+    space: SearchSpace,
+    /// How each candidate is scored.
+    scoring: Scoring,
+    /// The metric used to score the candidates.
+    evaluator: Box<dyn Evaluator>,
+    /// Seed of the RNG used by the random search.
+    seed: u64,
+}
+
+impl HyperparameterSearch {
+    /// Creates a new `HyperparameterSearch` instance.
+    ///
+    /// # Arguments
+    /// * `space` - The values tried for each hyperparameter.
+    /// * `scoring` - How each candidate is scored.
+    /// * `evaluator` - The metric used to score the candidates.
+    /// * `seed` - The seed of the RNG used by the random search.
+    pub fn new(
+        space: SearchSpace,
+        scoring: Scoring,
+        evaluator: Box<dyn Evaluator>,
+        seed: u64,
+    ) -> HyperparameterSearch {
+        HyperparameterSearch {
+            space,
+            scoring,
+            evaluator,
+            seed,
+        }
+    }
+
+    /// Score every combination of the hyperparameter values.
+    ///
+    /// # Arguments
+    /// * `training_dataset` - The `DataSet` used to train the candidates.
+    ///
+    /// # Errors
+    /// `LtrError` if a candidate could not be trained or evaluated.
+    pub fn grid_search(&self, training_dataset: &DataSet) -> Result<SearchReport, LtrError> {
+        self.search(training_dataset, self.space.grid())
+    }
+
+    /// Score a random sample of the combinations of the hyperparameter values.
+    ///
+    /// # Arguments
+    /// * `training_dataset` - The `DataSet` used to train the candidates.
+    /// * `num_candidates` - The number of combinations to try. At most the whole grid is tried.
+    ///
+    /// # Errors
+    /// `LtrError` if a candidate could not be trained or evaluated.
+    pub fn random_search(
+        &self,
+        training_dataset: &DataSet,
+        num_candidates: usize,
+    ) -> Result<SearchReport, LtrError> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut grid = self.space.grid();
+        grid.shuffle(&mut rng);
+        grid.truncate(num_candidates);
+        self.search(training_dataset, grid)
+    }
+
+    /// Build an untrained `AdaRank` model with the given hyperparameters.
+    ///
+    /// # Arguments
+    /// * `params` - The hyperparameters, e.g. the best ones of a `SearchReport`.
+    /// * `training_dataset` - The training `DataSet`.
+    /// * `validation_dataset` - The optional validation `DataSet`.
+    pub fn build(
+        &self,
+        params: &AdaRankParams,
+        training_dataset: DataSet,
+        validation_dataset: Option<DataSet>,
+    ) -> AdaRank {
+        AdaRank::new(
+            training_dataset,
+            (self.space.metrics[params.metric_index])(),
+            params.iter,
+            params.max_consecutive_selections,
+            params.tolerance,
+            params.features.clone(),
+            validation_dataset,
+        )
+    }
+
+    fn search(
+        &self,
+        training_dataset: &DataSet,
+        candidates: Vec<AdaRankParams>,
+    ) -> Result<SearchReport, LtrError> {
+        if candidates.is_empty() {
+            return Err(LtrError::ConfigurationError(
+                "The search space has no candidates.",
+            ));
+        }
+        // The folds are shared by all the candidates.
+        let folds = match &self.scoring {
+            Scoring::CrossValidation(kfold) => kfold.folds(training_dataset)?,
+            Scoring::Validation(_) => Vec::new(),
+        };
+
+        let mut scores = Vec::with_capacity(candidates.len());
+        for params in candidates {
+            let score = match &self.scoring {
+                Scoring::Validation(validation) => {
+                    let mut adarank = self.build(&params, training_dataset.clone(), None);
+                    adarank.fit()?;
+                    self.evaluator.evaluate_ranker(&adarank, validation)?
+                }
+                Scoring::CrossValidation(_) => cross_validate(
+                    &folds,
+                    |train| self.build(&params, train, None),
+                    &*self.evaluator,
+                )?
+                .mean_test(),
+            };
+            tracing::info!(
+                "iter = {}, max_consecutive_selections = {}, tolerance = {}, metric = {}, features = {}: {} = {:.5}",
+                params.iter,
+                params.max_consecutive_selections,
+                params.tolerance,
+                params.metric,
+                params.features_label(),
+                self.evaluator.to_string(),
+                score
+            );
+            scores.push(CandidateScore { params, score });
+        }
+
+        Ok(SearchReport {
+            metric: self.evaluator.to_string(),
+            candidates: scores,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{datapoint::DataPoint, eval::precision::Precision, ranklist::RankList, rl};
+
+    fn dataset() -> DataSet {
+        DataSet::from(vec![
+            rl!(
+                (1, 1, vec![0.9, 0.1, 0.5]),
+                (0, 1, vec![0.2, 0.8, 0.4]),
+                (0, 1, vec![0.1, 0.3, 0.6])
+            ),
+            rl!(
+                (0, 2, vec![0.3, 0.9, 0.2]),
+                (1, 2, vec![0.8, 0.2, 0.1]),
+                (0, 2, vec![0.4, 0.5, 0.7])
+            ),
+            rl!(
+                (0, 3, vec![0.9, 0.4, 0.3]),
+                (0, 3, vec![0.2, 0.6, 0.5]),
+                (1, 3, vec![0.7, 0.7, 0.8])
+            ),
+            rl!((1, 4, vec![0.6, 0.2, 0.9]), (0, 4, vec![0.1, 0.7, 0.3])),
+        ])
+    }
+
+    fn space() -> SearchSpace {
+        let mut space = SearchSpace::new();
+        space.iter = vec![1, 3];
+        space.tolerance = vec![0.0, 0.01];
+        space.features = vec![None, Some(vec![2])];
+        space.set_metrics(vec![
+            Arc::new(|| Box::new(MAP)),
+            Arc::new(|| Box::new(Precision::new(1))),
+        ]);
+        space
+    }
+
+    #[test]
+    fn test_grid_search() {
+        let search =
+            HyperparameterSearch::new(space(), Scoring::Validation(dataset()), Box::new(MAP), 0);
+        let report = search.grid_search(&dataset()).unwrap();
+        assert_eq!(report.candidates.len(), 16);
+        assert_eq!(report.metric, "MAP");
+
+        // Feature 2 alone ranks the relevant documents last.
+        let best = report.best().unwrap();
+        assert_eq!(best.params.features, None);
+        assert!(report
+            .candidates
+            .iter()
+            .all(|candidate| candidate.score <= best.score));
+        assert_eq!(format!("{}", report).lines().count(), 17);
+
+        let mut adarank = search.build(&best.params, dataset(), None);
+        adarank.fit().unwrap();
+        assert_eq!(
+            MAP.evaluate_ranker(&adarank, &dataset()).unwrap(),
+            best.score
+        );
+    }
+
+    #[test]
+    fn test_random_search() {
+        let search = HyperparameterSearch::new(
+            space(),
+            Scoring::CrossValidation(KFold::new(2, 1)),
+            Box::new(MAP),
+            42,
+        );
+        let report = search.random_search(&dataset(), 5).unwrap();
+        assert_eq!(report.candidates.len(), 5);
+
+        // The same seed gives the same candidates.
+        let again = search.random_search(&dataset(), 5).unwrap();
+        assert_eq!(report, again);
+        assert_eq!(
+            search
+                .random_search(&dataset(), 100)
+                .unwrap()
+                .candidates
+                .len(),
+            16
+        );
+        assert!(search.random_search(&dataset(), 0).is_err());
+    }
+}
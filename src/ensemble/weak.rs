@@ -1,14 +1,16 @@
 /// Copyright (c) 2021 Marcos Pontes
 /// MIT License
 ///
-use crate::ranker::Ranker;
+use serde::{Deserialize, Serialize};
+
+use crate::{datapoint::feature_at, ranker::Ranker};
 
 ///
 /// A WeakRanker can be interpreted as a stump in an ensemble of rankers.
 /// Usually, the weak ranker is dummy, and it only evaluates the `RankList`
 /// considering a single feature inside the `DataPoint`s.
 ///
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct WeakRanker {
     ///
     /// The feature index of the feature to be used in the ranking.
@@ -32,4 +34,11 @@ impl Ranker for WeakRanker {
             Err(_) => 0.0f32,
         }
     }
+
+    fn predict_features(&self, features: &[f32]) -> f32 {
+        match feature_at(features, self.feature_id) {
+            Ok(value) => *value,
+            Err(_) => 0.0f32,
+        }
+    }
 }
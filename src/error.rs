@@ -38,10 +38,33 @@ pub enum LtrError {
     ///
     IOError(String),
 
+    ///
+    /// Error raised when serializing or deserializing an object fails.
+    ///
+    SerializationError(String),
+
+    ///
+    /// Error raised when a NaN score is found while ranking the `DataPoint` at the given index.
+    ///
+    NanScore(usize),
+
+    ///
+    /// Error raised when a `Learner` is not properly configured.
+    ///
+    ConfigurationError(&'static str),
+
     ///
     /// Error raised when no `Ranker` was found in a `Learner`.
     ///
     NoRankers,
+    ///
+    /// Error raised when a feature name is not in the feature-name dictionary.
+    ///
+    UnknownFeature(String),
+    ///
+    /// Error raised when a `DataSet` fails its validation.
+    ///
+    InvalidDataSet(String),
 }
 
 impl Display for LtrError {
@@ -55,7 +78,12 @@ impl Display for LtrError {
             LtrError::EvaluationError(msg) => write!(f, "Evaluation error: {}", msg),
             LtrError::ParseError(msg) => write!(f, "Error while parsing an input: {}", msg),
             LtrError::IOError(msg) => write!(f, "Error while reading or writing an input: {}", msg),
+            LtrError::SerializationError(msg) => write!(f, "Serialization error: {}", msg),
+            LtrError::NanScore(i) => write!(f, "NaN score for the datapoint at index {}", i),
+            LtrError::ConfigurationError(msg) => write!(f, "Invalid configuration: {}", msg),
             LtrError::NoRankers => write!(f, "No rankers were built. Run `fit` first."),
+            LtrError::UnknownFeature(name) => write!(f, "Unknown feature: {}", name),
+            LtrError::InvalidDataSet(msg) => write!(f, "Invalid DataSet: {}", msg),
         }
     }
 }
@@ -90,9 +118,29 @@ mod tests {
             "Error while reading or writing an input: I/O",
             LtrError::IOError("I/O".to_string()).to_string()
         );
+        assert_eq!(
+            "Serialization error: EOF",
+            LtrError::SerializationError("EOF".to_string()).to_string()
+        );
+        assert_eq!(
+            "NaN score for the datapoint at index 3",
+            LtrError::NanScore(3).to_string()
+        );
+        assert_eq!(
+            "Invalid configuration: Bar",
+            LtrError::ConfigurationError("Bar").to_string()
+        );
         assert_eq!(
             "No rankers were built. Run `fit` first.",
             LtrError::NoRankers.to_string()
         );
+        assert_eq!(
+            "Unknown feature: BM25-title",
+            LtrError::UnknownFeature("BM25-title".to_string()).to_string()
+        );
+        assert_eq!(
+            "Invalid DataSet: RankList 0 is empty",
+            LtrError::InvalidDataSet("RankList 0 is empty".to_string()).to_string()
+        );
     }
 }
@@ -1,11 +1,13 @@
-use crate::eval::Evaluator;
+use std::fmt;
+
+use crate::eval::{is_relevant, Evaluator};
 use crate::ranklist::RankList;
 
 /// MAP (Mean Average Precision) for a set of queries is the mean of the average precision
 /// scores for each query.
 ///
 /// The average precision score is the sum of the precision scores for each k, divided by
-/// the number of positive labels.
+/// the number of positive labels. Any positive label is relevant.
 ///
 /// See [this post](https://towardsdatascience.com/breaking-down-mean-average-precision-map-ae462f623a52) for more information.
 #[derive(Debug, Clone)]
@@ -13,19 +15,17 @@ pub struct MAP;
 
 impl Evaluator for MAP {
     fn evaluate_ranklist(&self, ranklist: &RankList) -> f32 {
+        let labels: Vec<f32> = ranklist.into_iter().map(|dp| dp.get_label()).collect();
+        self.evaluate_labels(&labels)
+    }
+
+    fn evaluate_labels(&self, labels: &[f32]) -> f32 {
         let mut average_precision = 0.0f32;
         let mut num_relevant_docs = 0;
-        for i in 0..ranklist.len() {
-            match ranklist.get(i) {
-                Ok(dp) => {
-                    if dp.get_label() > 0 {
-                        num_relevant_docs += 1;
-                        average_precision += num_relevant_docs as f32 / (i as f32 + 1.0);
-                    }
-                }
-                Err(_) => {
-                    break;
-                }
+        for (i, label) in labels.iter().enumerate() {
+            if is_relevant(*label) {
+                num_relevant_docs += 1;
+                average_precision += num_relevant_docs as f32 / (i as f32 + 1.0);
             }
         }
         match num_relevant_docs {
@@ -33,11 +33,47 @@ impl Evaluator for MAP {
             _ => average_precision / num_relevant_docs as f32,
         }
     }
+
+    /// Exact expected average precision over the orderings of the ties.
+    ///
+    /// For a position `p` in a group of `n` ties starting at `s`, with `r` relevant
+    /// documents and `c` relevant documents ranked before the group, the expected
+    /// precision contribution is `(r/n * (c + 1) + (p - s) * r(r-1) / (n(n-1))) / p`.
+    fn evaluate_ties(&self, labels: &[f32], groups: &[usize]) -> f32 {
+        let num_relevant_docs = labels.iter().filter(|&&label| is_relevant(label)).count();
+        if num_relevant_docs == 0 {
+            return 0.0;
+        }
+
+        let mut average_precision = 0.0f32;
+        let mut relevant_before = 0.0f32;
+        let mut start = 0;
+        for &size in groups {
+            let n = size as f32;
+            let r = labels[start..start + size]
+                .iter()
+                .filter(|&&label| is_relevant(label))
+                .count() as f32;
+            let pair = if size > 1 {
+                r * (r - 1.0) / (n * (n - 1.0))
+            } else {
+                0.0
+            };
+            for offset in 0..size {
+                let position = (start + offset + 1) as f32;
+                average_precision +=
+                    (r / n * (relevant_before + 1.0) + offset as f32 * pair) / position;
+            }
+            relevant_before += r;
+            start += size;
+        }
+        average_precision / num_relevant_docs as f32
+    }
 }
 
-impl ToString for MAP {
-    fn to_string(&self) -> String {
-        "MAP".to_string()
+impl fmt::Display for MAP {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MAP")
     }
 }
 
@@ -45,9 +81,12 @@ impl ToString for MAP {
 mod tests {
     use super::*;
     use crate::datapoint::DataPoint;
+    use crate::ensemble::weak::WeakRanker;
+    use crate::ranker::Ranker;
     use crate::ranklist::RankList;
     use crate::rl;
     use crate::utils::random;
+    use crate::DataSet;
 
     use approx::relative_eq;
 
@@ -68,4 +107,39 @@ mod tests {
 
         assert!(relative_eq!(map_score, 0.588, max_relative = 0.01f32));
     }
+
+    #[test]
+    fn test_map_evaluate_ranker() {
+        let dataset = DataSet::from(vec![
+            rl!(
+                (0, 1, vec![0.1, 0.5]),
+                (1, 1, vec![0.9, 0.1]),
+                (1, 1, vec![0.5, 0.9])
+            ),
+            rl!((1, 2, vec![0.2, 0.3]), (0, 2, vec![0.4, 0.3])),
+        ]);
+        let ranker = WeakRanker::new(2);
+
+        let score = MAP.evaluate_ranker(&ranker, &dataset).unwrap();
+        assert!(relative_eq!(score, 0.916, max_relative = 0.01f32));
+        // The dataset was not reordered.
+        assert_eq!(dataset[0].get(0).unwrap().get_label(), 0.0);
+
+        ranker.rank_dataset(&dataset);
+        assert_eq!(MAP.evaluate_dataset(&dataset).unwrap(), score);
+        assert!(MAP.evaluate_ranker(&ranker, &DataSet::new()).is_err());
+    }
+
+    #[test]
+    fn test_map_ties() {
+        // Orderings of the tied group: [1, 0, 1], [1, 1, 0] and [0, 1, 1].
+        let expected = (0.8333 + 1.0 + 0.5833) / 3.0;
+        assert!(relative_eq!(
+            MAP.evaluate_ties(&[1.0, 0.0, 1.0, 0.0], &[3, 1]),
+            expected,
+            max_relative = 0.001f32
+        ));
+        assert_eq!(MAP.evaluate_ties(&[1.0, 0.0, 1.0], &[1, 1, 1]), 0.8333334);
+        assert_eq!(MAP.evaluate_ties(&[0.0, 0.0], &[2]), 0.0);
+    }
 }
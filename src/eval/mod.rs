@@ -1,13 +1,50 @@
 /// Metric MAP (Mean Average Precision).
 pub mod map;
 
+/// Metric NDCG@k (Normalized Discounted Cumulative Gain at k).
+pub mod ndcg;
+
 /// Metric P@k (Precision at k).
 pub mod precision;
 
+/// Scoring of the queries without relevant documents.
+pub mod no_relevant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::datapoint::{DataPoint, QueryId};
 use crate::error::LtrError;
+use crate::ranker::{tie_groups, Ranker, RankingPolicy, TieBreaking};
 use crate::ranklist::RankList;
 use crate::DataSet;
 
+/// Check whether a label is relevant for binary metrics such as MAP and P@k:
+/// any positive label, integer or real-valued, is relevant.
+pub fn is_relevant(label: f32) -> bool {
+    label > 0.0
+}
+
+/// The metric value of a single query, as reported by `Evaluator::evaluate_queries`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryScore {
+    /// The query id of the evaluated `RankList`.
+    pub query_id: QueryId,
+    /// The metric value of the query.
+    pub score: f32,
+}
+
+/// Bound of the types shared across threads by the `parallel` feature: `Send + Sync`.
+#[cfg(feature = "parallel")]
+pub trait MaybeSync: Send + Sync {}
+#[cfg(feature = "parallel")]
+impl<T: Send + Sync + ?Sized> MaybeSync for T {}
+
+/// Without the `parallel` feature, nothing is shared across threads: every type is `MaybeSync`.
+#[cfg(not(feature = "parallel"))]
+pub trait MaybeSync {}
+#[cfg(not(feature = "parallel"))]
+impl<T: ?Sized> MaybeSync for T {}
+
 /// The Evaluator trait allows us to create or own ways to assess
 /// the ranking effectiveness. In the literature, many different evaluators
 /// were already proposed: NDCG, MAP, F1, Precision, Recall, etc.
@@ -17,7 +54,10 @@ use crate::DataSet;
 /// Under the hood, to implement an  `Evaluator` you need to
 /// implement the function `evaluate_ranklist`, which evaluates the results
 /// generated  from a single `RankList`.
-pub trait Evaluator: ToString {
+///
+/// With the `parallel` feature, evaluators are shared across threads while
+/// indexing the training features, so they must also be `Send` and `Sync`.
+pub trait Evaluator: ToString + MaybeSync {
     /// Evaluates a `DataSet`
     ///
     /// # Arguments
@@ -52,4 +92,224 @@ pub trait Evaluator: ToString {
     ///
     /// The metric value.
     fn evaluate_ranklist(&self, ranklist: &RankList) -> f32;
+
+    /// Evaluates a ranking given only the labels of its `DataPoint`s, in ranked order.
+    ///
+    /// This is useful to evaluate rankings without reordering a `RankList`. The default
+    /// implementation builds a `RankList` with the labels and calls `evaluate_ranklist`,
+    /// so evaluators that only rely on labels should override it.
+    ///
+    /// # Arguments
+    ///
+    /// * `labels` - The labels of the ranked `DataPoint`s, integer grades or real values.
+    ///
+    /// # Returns
+    ///
+    /// The metric value.
+    fn evaluate_labels(&self, labels: &[f32]) -> f32 {
+        let ranklist = RankList::new(
+            labels
+                .iter()
+                .map(|&label| DataPoint::new(label, 0, Vec::new(), None))
+                .collect(),
+        );
+        self.evaluate_ranklist(&ranklist)
+    }
+
+    /// Get the number of top ranked `DataPoint`s the metric depends on, if any.
+    ///
+    /// Evaluators with a cutoff, such as P@k, only need the `k` best ranked `DataPoint`s,
+    /// so rankings are computed with `Ranker::top_k_with` instead of a full sort.
+    ///
+    /// # Returns
+    ///
+    /// The cutoff of the metric, or `None` if the metric depends on the whole ranking.
+    fn cutoff(&self) -> Option<usize> {
+        None
+    }
+
+    /// Evaluates a ranking with ties, averaging the metric over all the orderings of
+    /// the tied `DataPoint`s.
+    ///
+    /// The default implementation approximates the average with the mean of the
+    /// best-case and the worst-case orderings. Evaluators with a closed form for the
+    /// expected value should override it.
+    ///
+    /// # Arguments
+    ///
+    /// * `labels` - The labels of the ranked `DataPoint`s.
+    /// * `groups` - The sizes of the groups of tied `DataPoint`s, in order, as returned
+    ///   by `tie_groups`.
+    ///
+    /// # Returns
+    ///
+    /// The metric value.
+    fn evaluate_ties(&self, labels: &[f32], groups: &[usize]) -> f32 {
+        let mut best = Vec::with_capacity(labels.len());
+        let mut worst = Vec::with_capacity(labels.len());
+        let mut start = 0;
+        for &size in groups {
+            let mut group = labels[start..start + size].to_vec();
+            group.sort_by(|a, b| a.total_cmp(b));
+            worst.extend_from_slice(&group);
+            best.extend(group.iter().rev());
+            start += size;
+        }
+        (self.evaluate_labels(&best) + self.evaluate_labels(&worst)) / 2.0
+    }
+
+    /// Evaluates a ranking of `DataPoint`s, as returned by `Ranker::scored_ranking`.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_points` - The ranked `DataPoint`s, in their original order.
+    /// * `ranking` - The `(index, score)` pairs from the best to the worst ranked.
+    ///
+    /// # Returns
+    ///
+    /// The metric value.
+    fn evaluate_ranking(&self, data_points: &[DataPoint], ranking: &[(usize, f32)]) -> f32 {
+        let labels: Vec<f32> = ranking
+            .iter()
+            .map(|&(i, _)| data_points[i].get_label())
+            .collect();
+        self.evaluate_labels(&labels)
+    }
+
+    /// Evaluates the rankings produced by a `Ranker` on a `DataSet`.
+    ///
+    /// Unlike `Ranker::rank_dataset` followed by `evaluate_dataset`, the `DataSet`
+    /// is not reordered.
+    ///
+    /// # Arguments
+    ///
+    /// * `ranker` - The `Ranker` to be evaluated.
+    /// * `dataset` - The `DataSet` to be ranked.
+    ///
+    /// # Returns
+    /// Average of the metric defined on the `evaluate_ranklist` function.
+    fn evaluate_ranker(&self, ranker: &dyn Ranker, dataset: &DataSet) -> Result<f32, LtrError> {
+        self.evaluate_ranker_with(ranker, dataset, &RankingPolicy::default())
+    }
+
+    /// Evaluates a ranking of `DataPoint`s produced with a `RankingPolicy`.
+    /// With `TieBreaking::Average`, the metric is averaged over the ties.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_points` - The ranked `DataPoint`s, in their original order.
+    /// * `ranking` - The `(index, score)` pairs from the best to the worst ranked.
+    /// * `policy` - The policy used to produce the ranking.
+    ///
+    /// # Returns
+    ///
+    /// The metric value.
+    fn evaluate_ranking_with(
+        &self,
+        data_points: &[DataPoint],
+        ranking: &[(usize, f32)],
+        policy: &RankingPolicy,
+    ) -> f32 {
+        match policy.ties {
+            TieBreaking::Average => {
+                let labels: Vec<f32> = ranking
+                    .iter()
+                    .map(|&(i, _)| data_points[i].get_label())
+                    .collect();
+                self.evaluate_ties(&labels, &tie_groups(ranking))
+            }
+            _ => self.evaluate_ranking(data_points, ranking),
+        }
+    }
+
+    /// Evaluates the rankings produced by a `Ranker` on a `DataSet` with a `RankingPolicy`,
+    /// without reordering the `DataSet`.
+    ///
+    /// # Arguments
+    ///
+    /// * `ranker` - The `Ranker` to be evaluated.
+    /// * `dataset` - The `DataSet` to be ranked.
+    /// * `policy` - How NaN scores and ties are handled.
+    ///
+    /// # Returns
+    /// Average of the metric defined on the `evaluate_ranklist` function.
+    ///
+    /// # Errors
+    /// `LtrError` if the dataset is empty or a ranking fails.
+    fn evaluate_ranker_with(
+        &self,
+        ranker: &dyn Ranker,
+        dataset: &DataSet,
+        policy: &RankingPolicy,
+    ) -> Result<f32, LtrError> {
+        if dataset.is_empty() {
+            return Err(LtrError::EvaluationError(
+                "Error in Evaluator::evaluate_ranker: the dataset is empty.",
+            ));
+        }
+        let mut score = 0.0f32;
+        for ranklist in dataset {
+            score += self.evaluate_ranker_on(ranker, &ranklist.data_points(), policy)?;
+        }
+        Ok(score / dataset.len() as f32)
+    }
+
+    /// Evaluates the rankings produced by a `Ranker` on each query of a `DataSet`,
+    /// without reordering the `DataSet`. Empty `RankList`s are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `ranker` - The `Ranker` to be evaluated.
+    /// * `dataset` - The `DataSet` to be ranked.
+    /// * `policy` - How NaN scores and ties are handled.
+    ///
+    /// # Returns
+    /// The metric value of each query, in the order of the `DataSet`.
+    ///
+    /// # Errors
+    /// `LtrError::NanScore` if a score is NaN and the policy is `NanPolicy::Error`.
+    fn evaluate_queries(
+        &self,
+        ranker: &dyn Ranker,
+        dataset: &DataSet,
+        policy: &RankingPolicy,
+    ) -> Result<Vec<QueryScore>, LtrError> {
+        let mut scores = Vec::with_capacity(dataset.len());
+        for ranklist in dataset {
+            if let Some(query_id) = ranklist.query_id() {
+                let score = self.evaluate_ranker_on(ranker, &ranklist.data_points(), policy)?;
+                scores.push(QueryScore { query_id, score });
+            }
+        }
+        Ok(scores)
+    }
+
+    /// Evaluates the ranking produced by a `Ranker` on a slice of `DataPoint`s with a
+    /// `RankingPolicy`. Only the top ranked `DataPoint`s are sorted when the metric has
+    /// a cutoff.
+    ///
+    /// # Arguments
+    ///
+    /// * `ranker` - The `Ranker` to be evaluated.
+    /// * `data_points` - The `DataPoint`s to be ranked.
+    /// * `policy` - How NaN scores and ties are handled.
+    ///
+    /// # Returns
+    ///
+    /// The metric value.
+    ///
+    /// # Errors
+    /// `LtrError::NanScore` if a score is NaN and the policy is `NanPolicy::Error`.
+    fn evaluate_ranker_on(
+        &self,
+        ranker: &dyn Ranker,
+        data_points: &[DataPoint],
+        policy: &RankingPolicy,
+    ) -> Result<f32, LtrError> {
+        let ranking = match self.cutoff() {
+            Some(k) => ranker.top_k_with(data_points, k, policy)?,
+            None => ranker.scored_ranking_with(data_points, policy)?,
+        };
+        Ok(self.evaluate_ranking_with(data_points, &ranking, policy))
+    }
 }
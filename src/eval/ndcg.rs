@@ -0,0 +1,190 @@
+/// Copyright (c) 2021 Marcos Pontes
+// This code is licensed under MIT license (see LICENSE for details)
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::eval::Evaluator;
+use crate::ranklist::RankList;
+
+/// How a relevance label is turned into a gain.
+/// Labels lower than or equal to zero have no gain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Gain {
+    /// `2^label - 1`, which emphasizes highly relevant documents.
+    #[default]
+    Exponential,
+    /// `label`, which suits real-valued targets such as click-through rates.
+    Linear,
+}
+
+impl Gain {
+    /// Get the gain of a label.
+    pub fn of(&self, label: f32) -> f32 {
+        if label <= 0.0 {
+            return 0.0;
+        }
+        match self {
+            Gain::Exponential => label.exp2() - 1.0,
+            Gain::Linear => label,
+        }
+    }
+}
+
+/// NDCG (Normalized Discounted Cumulative Gain) interprets the labels as graded gains:
+/// `DCG@k = sum(gain(label_i) / log2(i + 1))` over the first k positions, normalized by
+/// the DCG@k of the ideal ranking.
+///
+/// See [Wikipedia](https://en.wikipedia.org/wiki/Discounted_cumulative_gain) for more information.
+#[derive(Debug, Clone)]
+pub struct NDCG {
+    limit: usize,
+    gain: Gain,
+}
+
+impl NDCG {
+    /// Creates a new `NDCG` instance with exponential gains.
+    ///
+    /// # Arguments
+    /// * `limit` - The limit K. The number of documents to consider in the rank list.
+    pub fn new(limit: usize) -> NDCG {
+        NDCG {
+            limit,
+            gain: Gain::default(),
+        }
+    }
+
+    /// Get the limit K.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Set how labels are turned into gains.
+    ///
+    /// # Arguments
+    /// * `gain` - The gain function.
+    pub fn set_gain(&mut self, gain: Gain) {
+        self.gain = gain;
+    }
+
+    /// Discount of a (zero-based) position.
+    fn discount(position: usize) -> f32 {
+        1.0 / (position as f32 + 2.0).log2()
+    }
+
+    /// DCG@k of the ideal ranking of the labels.
+    fn ideal_dcg(&self, labels: &[f32]) -> f32 {
+        let mut gains: Vec<f32> = labels.iter().map(|&label| self.gain.of(label)).collect();
+        gains.sort_by(|a, b| b.total_cmp(a));
+        gains
+            .iter()
+            .take(self.limit)
+            .enumerate()
+            .map(|(i, gain)| gain * NDCG::discount(i))
+            .sum()
+    }
+}
+
+impl Evaluator for NDCG {
+    fn evaluate_ranklist(&self, ranklist: &RankList) -> f32 {
+        let labels: Vec<f32> = ranklist.into_iter().map(|dp| dp.get_label()).collect();
+        self.evaluate_labels(&labels)
+    }
+
+    fn evaluate_labels(&self, labels: &[f32]) -> f32 {
+        let ideal = self.ideal_dcg(labels);
+        if ideal == 0.0 {
+            return 0.0;
+        }
+        let dcg: f32 = labels
+            .iter()
+            .take(self.limit)
+            .enumerate()
+            .map(|(i, &label)| self.gain.of(label) * NDCG::discount(i))
+            .sum();
+        dcg / ideal
+    }
+
+    /// The ideal DCG depends on every label, so the whole ranking is needed.
+    fn cutoff(&self) -> Option<usize> {
+        None
+    }
+
+    /// Exact expected NDCG over the orderings of the ties: DCG is linear in the gains,
+    /// so each position of a group of ties gets the mean gain of the group.
+    fn evaluate_ties(&self, labels: &[f32], groups: &[usize]) -> f32 {
+        let ideal = self.ideal_dcg(labels);
+        if ideal == 0.0 {
+            return 0.0;
+        }
+        let mut dcg = 0.0f32;
+        let mut start = 0;
+        for &size in groups {
+            if start >= self.limit {
+                break;
+            }
+            let mean_gain = labels[start..start + size]
+                .iter()
+                .map(|&label| self.gain.of(label))
+                .sum::<f32>()
+                / size as f32;
+            for position in start..(start + size).min(self.limit) {
+                dcg += mean_gain * NDCG::discount(position);
+            }
+            start += size;
+        }
+        dcg / ideal
+    }
+}
+
+impl fmt::Display for NDCG {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "NDCG@{}", self.limit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use approx::relative_eq;
+
+    #[test]
+    fn test_ndcg() {
+        let ndcg = NDCG::new(3);
+        assert_eq!(ndcg.to_string(), "NDCG@3");
+        assert_eq!(ndcg.evaluate_labels(&[3.0, 2.0, 1.0, 0.0]), 1.0);
+        assert_eq!(ndcg.evaluate_labels(&[0.0, 0.0]), 0.0);
+
+        // DCG@3 = 1 / log2(3) + 7 / log2(4), ideal = 7 + 1 / log2(3).
+        let expected = (1.0 / 3f32.log2() + 3.5) / (7.0 + 1.0 / 3f32.log2());
+        assert!(relative_eq!(
+            ndcg.evaluate_labels(&[0.0, 1.0, 3.0]),
+            expected,
+            max_relative = 0.001f32
+        ));
+
+        // Real-valued and negative labels.
+        let mut linear = NDCG::new(2);
+        linear.set_gain(Gain::Linear);
+        let expected = (0.2 + 0.6 / 3f32.log2()) / (0.6 + 0.2 / 3f32.log2());
+        assert!(relative_eq!(
+            linear.evaluate_labels(&[0.2, 0.6, -1.0]),
+            expected,
+            max_relative = 0.001f32
+        ));
+    }
+
+    #[test]
+    fn test_ndcg_ties() {
+        let ndcg = NDCG::new(10);
+        // Orderings of the tied group: [1, 0] and [0, 1].
+        let expected =
+            (ndcg.evaluate_labels(&[1.0, 0.0]) + ndcg.evaluate_labels(&[0.0, 1.0])) / 2.0;
+        assert!(relative_eq!(
+            ndcg.evaluate_ties(&[1.0, 0.0], &[2]),
+            expected,
+            max_relative = 0.001f32
+        ));
+    }
+}
@@ -0,0 +1,259 @@
+use std::fmt;
+
+use crate::datapoint::DataPoint;
+use crate::error::LtrError;
+use crate::eval::{is_relevant, Evaluator, QueryScore};
+use crate::ranker::{Ranker, RankingPolicy};
+use crate::ranklist::RankList;
+use crate::DataSet;
+
+/// How the queries without relevant documents are counted by a `NoRelevantEvaluator`.
+///
+/// Evaluators such as MAP, NDCG and P@k score these queries 0 whatever the ranking,
+/// which dilutes the averages over a `DataSet`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NoRelevantPolicy {
+    /// Leave the queries out of the averages and of the per-query scores, as trec_eval does.
+    Skip,
+    /// Score the queries 0, as the wrapped evaluator does.
+    #[default]
+    Zero,
+    /// Score the queries 1, since any ranking of them is as good as the ideal one.
+    One,
+}
+
+/// An `Evaluator` counting the queries without relevant documents following a
+/// `NoRelevantPolicy`. The other queries are scored by the wrapped evaluator.
+///
+/// Single queries can't be skipped: with `NoRelevantPolicy::Skip`, methods scoring
+/// one query, such as `evaluate_ranklist`, return 0 for them.
+pub struct NoRelevantEvaluator {
+    /// The evaluator of the queries with relevant documents.
+    evaluator: Box<dyn Evaluator>,
+    /// How the queries without relevant documents are counted.
+    policy: NoRelevantPolicy,
+}
+
+impl NoRelevantEvaluator {
+    /// Create a new `NoRelevantEvaluator`.
+    ///
+    /// # Arguments
+    /// * `evaluator` - The evaluator of the queries with relevant documents.
+    /// * `policy` - How the queries without relevant documents are counted.
+    pub fn new(evaluator: Box<dyn Evaluator>, policy: NoRelevantPolicy) -> NoRelevantEvaluator {
+        NoRelevantEvaluator { evaluator, policy }
+    }
+
+    /// Get the wrapped evaluator.
+    pub fn evaluator(&self) -> &dyn Evaluator {
+        &*self.evaluator
+    }
+
+    /// Get how the queries without relevant documents are counted.
+    pub fn policy(&self) -> NoRelevantPolicy {
+        self.policy
+    }
+
+    /// The score of a query without relevant documents.
+    fn no_relevant_score(&self) -> f32 {
+        match self.policy {
+            NoRelevantPolicy::One => 1.0,
+            NoRelevantPolicy::Skip | NoRelevantPolicy::Zero => 0.0,
+        }
+    }
+
+    /// Average the scores of the queries of a `DataSet`, following the policy.
+    fn average(
+        &self,
+        dataset: &DataSet,
+        mut score: impl FnMut(&RankList) -> Result<f32, LtrError>,
+    ) -> Result<f32, LtrError> {
+        if dataset.is_empty() {
+            return Err(LtrError::EvaluationError(
+                "Error in NoRelevantEvaluator: the dataset is empty.",
+            ));
+        }
+        let mut total = 0.0f32;
+        let mut count = 0;
+        for ranklist in dataset {
+            if ranklist.has_relevant() {
+                total += score(ranklist)?;
+            } else if self.policy == NoRelevantPolicy::Skip {
+                continue;
+            } else {
+                total += self.no_relevant_score();
+            }
+            count += 1;
+        }
+        match count {
+            0 => Err(LtrError::EvaluationError(
+                "Error in NoRelevantEvaluator: no query has relevant documents.",
+            )),
+            _ => Ok(total / count as f32),
+        }
+    }
+}
+
+impl Evaluator for NoRelevantEvaluator {
+    fn evaluate_dataset(&self, dataset: &DataSet) -> Result<f32, LtrError> {
+        self.average(dataset, |ranklist| {
+            Ok(self.evaluator.evaluate_ranklist(ranklist))
+        })
+    }
+
+    fn evaluate_ranklist(&self, ranklist: &RankList) -> f32 {
+        match ranklist.has_relevant() {
+            true => self.evaluator.evaluate_ranklist(ranklist),
+            false => self.no_relevant_score(),
+        }
+    }
+
+    fn evaluate_labels(&self, labels: &[f32]) -> f32 {
+        match labels.iter().any(|&label| is_relevant(label)) {
+            true => self.evaluator.evaluate_labels(labels),
+            false => self.no_relevant_score(),
+        }
+    }
+
+    fn cutoff(&self) -> Option<usize> {
+        self.evaluator.cutoff()
+    }
+
+    fn evaluate_ties(&self, labels: &[f32], groups: &[usize]) -> f32 {
+        match labels.iter().any(|&label| is_relevant(label)) {
+            true => self.evaluator.evaluate_ties(labels, groups),
+            false => self.no_relevant_score(),
+        }
+    }
+
+    fn evaluate_ranking(&self, data_points: &[DataPoint], ranking: &[(usize, f32)]) -> f32 {
+        match has_relevant(data_points) {
+            true => self.evaluator.evaluate_ranking(data_points, ranking),
+            false => self.no_relevant_score(),
+        }
+    }
+
+    fn evaluate_ranking_with(
+        &self,
+        data_points: &[DataPoint],
+        ranking: &[(usize, f32)],
+        policy: &RankingPolicy,
+    ) -> f32 {
+        match has_relevant(data_points) {
+            true => self
+                .evaluator
+                .evaluate_ranking_with(data_points, ranking, policy),
+            false => self.no_relevant_score(),
+        }
+    }
+
+    fn evaluate_ranker_with(
+        &self,
+        ranker: &dyn Ranker,
+        dataset: &DataSet,
+        policy: &RankingPolicy,
+    ) -> Result<f32, LtrError> {
+        self.average(dataset, |ranklist| {
+            self.evaluator
+                .evaluate_ranker_on(ranker, &ranklist.data_points(), policy)
+        })
+    }
+
+    fn evaluate_queries(
+        &self,
+        ranker: &dyn Ranker,
+        dataset: &DataSet,
+        policy: &RankingPolicy,
+    ) -> Result<Vec<QueryScore>, LtrError> {
+        let mut scores = Vec::with_capacity(dataset.len());
+        for ranklist in dataset {
+            let query_id = match ranklist.query_id() {
+                Some(query_id) => query_id,
+                None => continue,
+            };
+            let score = if ranklist.has_relevant() {
+                self.evaluator
+                    .evaluate_ranker_on(ranker, &ranklist.data_points(), policy)?
+            } else if self.policy == NoRelevantPolicy::Skip {
+                continue;
+            } else {
+                self.no_relevant_score()
+            };
+            scores.push(QueryScore { query_id, score });
+        }
+        Ok(scores)
+    }
+
+    fn evaluate_ranker_on(
+        &self,
+        ranker: &dyn Ranker,
+        data_points: &[DataPoint],
+        policy: &RankingPolicy,
+    ) -> Result<f32, LtrError> {
+        match has_relevant(data_points) {
+            true => self
+                .evaluator
+                .evaluate_ranker_on(ranker, data_points, policy),
+            false => Ok(self.no_relevant_score()),
+        }
+    }
+}
+
+/// Check whether any of the `DataPoint`s is relevant.
+fn has_relevant(data_points: &[DataPoint]) -> bool {
+    data_points.iter().any(|dp| is_relevant(dp.get_label()))
+}
+
+impl fmt::Display for NoRelevantEvaluator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.evaluator.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ensemble::weak::WeakRanker;
+    use crate::eval::map::MAP;
+    use crate::rl;
+
+    #[test]
+    fn test_no_relevant_evaluator() {
+        let dataset = DataSet::from(vec![
+            rl!((1, "q1", vec![0.9]), (0, "q1", vec![0.1])),
+            rl!((0, "q2", vec![0.2]), (0, "q2", vec![0.3])),
+            rl!((0, "q3", vec![0.9]), (1, "q3", vec![0.1])),
+        ]);
+        let ranker = WeakRanker::new(1);
+        let policy = RankingPolicy::default();
+        let evaluate = |policy| NoRelevantEvaluator::new(Box::new(MAP), policy);
+
+        let zero = evaluate(NoRelevantPolicy::Zero);
+        assert_eq!(zero.evaluate_ranker(&ranker, &dataset).unwrap(), 0.5);
+        assert_eq!(
+            zero.evaluate_ranker(&ranker, &dataset).unwrap(),
+            MAP.evaluate_ranker(&ranker, &dataset).unwrap()
+        );
+        assert_eq!(zero.to_string(), "MAP");
+
+        let one = evaluate(NoRelevantPolicy::One);
+        assert!(approx::relative_eq!(
+            one.evaluate_ranker(&ranker, &dataset).unwrap(),
+            2.5 / 3.0
+        ));
+        assert_eq!(one.evaluate_ranklist(&dataset[1]), 1.0);
+
+        let skip = evaluate(NoRelevantPolicy::Skip);
+        assert_eq!(skip.evaluate_ranker(&ranker, &dataset).unwrap(), 0.75);
+        assert_eq!(skip.evaluate_dataset(&dataset).unwrap(), 0.75);
+        let scores = skip.evaluate_queries(&ranker, &dataset, &policy).unwrap();
+        assert_eq!(
+            scores.iter().map(|s| s.score).collect::<Vec<f32>>(),
+            vec![1.0, 0.5]
+        );
+
+        let unjudged = DataSet::from(vec![dataset[1].clone()]);
+        assert!(skip.evaluate_ranker(&ranker, &unjudged).is_err());
+        assert_eq!(one.evaluate_dataset(&unjudged).unwrap(), 1.0);
+    }
+}
@@ -1,10 +1,12 @@
 /// Copyright (c) 2021 Marcos Pontes
 // This code is licensed under MIT license (see LICENSE for details)
-use crate::eval::Evaluator;
+use std::fmt;
+
+use crate::eval::{is_relevant, Evaluator};
 use crate::ranklist::RankList;
 
 /// Precision is the fraction of the documents retrieved that are relevant to the user's information need.
-/// `precision = relevant_retrieved / retrieved`.
+/// `precision = relevant_retrieved / retrieved`. Any positive label is relevant.
 ///
 /// See [Wikipedia](https://en.wikipedia.org/wiki/Precision_and_recall#Precision) for more information.
 #[derive(Debug, Clone)]
@@ -37,17 +39,19 @@ impl Precision {
 
 impl Evaluator for Precision {
     fn evaluate_ranklist(&self, ranklist: &RankList) -> f32 {
+        let labels: Vec<f32> = ranklist
+            .into_iter()
+            .take(self.limit)
+            .map(|dp| dp.get_label())
+            .collect();
+        self.evaluate_labels(&labels)
+    }
+
+    fn evaluate_labels(&self, labels: &[f32]) -> f32 {
         let mut precision_score = 0.0f32;
-        for i in 0..self.limit {
-            match ranklist.get(i) {
-                Ok(dp) => {
-                    if dp.get_label() == 1 {
-                        precision_score += 1.0;
-                    }
-                }
-                Err(_) => {
-                    break;
-                }
+        for label in labels.iter().take(self.limit) {
+            if is_relevant(*label) {
+                precision_score += 1.0;
             }
         }
         match self.limit {
@@ -55,11 +59,39 @@ impl Evaluator for Precision {
             _ => precision_score / self.limit as f32,
         }
     }
+
+    fn cutoff(&self) -> Option<usize> {
+        Some(self.limit)
+    }
+
+    /// Exact expected precision over the orderings of the ties: a group of `n` ties
+    /// with `r` relevant documents and `m` positions within the limit contributes `r * m / n`.
+    fn evaluate_ties(&self, labels: &[f32], groups: &[usize]) -> f32 {
+        if self.limit == 0 {
+            return 0.0;
+        }
+
+        let mut precision_score = 0.0f32;
+        let mut start = 0;
+        for &size in groups {
+            if start >= self.limit {
+                break;
+            }
+            let relevant = labels[start..start + size]
+                .iter()
+                .filter(|&&label| is_relevant(label))
+                .count();
+            let within_limit = (self.limit - start).min(size);
+            precision_score += (relevant * within_limit) as f32 / size as f32;
+            start += size;
+        }
+        precision_score / self.limit as f32
+    }
 }
 
-impl ToString for Precision {
-    fn to_string(&self) -> String {
-        format!("P@{}", self.limit)
+impl fmt::Display for Precision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "P@{}", self.limit)
     }
 }
 
@@ -68,9 +100,12 @@ mod tests {
     use super::*;
 
     use crate::datapoint::DataPoint;
+    use crate::ensemble::weak::WeakRanker;
+    use crate::ranker::Ranker;
     use crate::ranklist::RankList;
     use crate::rl;
     use crate::utils::random;
+    use crate::DataSet;
 
     use approx::relative_eq;
 
@@ -109,4 +144,39 @@ mod tests {
             max_relative = 0.01f32
         ));
     }
+
+    #[test]
+    fn test_precision_ties() {
+        let p2 = Precision::new(2);
+        // Orderings of the tied group: [1, 0, 1], [1, 1, 0] and [0, 1, 1].
+        assert!(relative_eq!(
+            p2.evaluate_ties(&[1.0, 0.0, 1.0, 0.0], &[3, 1]),
+            0.666,
+            max_relative = 0.01f32
+        ));
+        assert_eq!(p2.evaluate_ties(&[0.0, 1.0, 1.0], &[1, 2]), 0.5);
+        assert_eq!(Precision::new(0).evaluate_ties(&[1.0], &[1]), 0.0);
+    }
+
+    #[test]
+    fn test_precision_evaluate_ranker() {
+        let dataset = DataSet::from(vec![
+            rl!(
+                (0, 1, vec![0.1, 0.5]),
+                (1, 1, vec![0.9, 0.1]),
+                (1, 1, vec![0.5, 0.9]),
+                (0, 1, vec![0.7, 0.2])
+            ),
+            rl!((1, 2, vec![0.2, 0.3]), (0, 2, vec![0.4, 0.3])),
+        ]);
+        let p2 = Precision::new(2);
+        assert_eq!(p2.cutoff(), Some(2));
+
+        // Only the top 2 documents of each query are ranked.
+        let ranker = WeakRanker::new(1);
+        let score = p2.evaluate_ranker(&ranker, &dataset).unwrap();
+        ranker.rank_dataset(&dataset);
+        assert_eq!(p2.evaluate_dataset(&dataset).unwrap(), score);
+        assert_eq!(score, 0.5);
+    }
 }
@@ -0,0 +1,225 @@
+/// Copyright (c) 2021 Marcos Pontes
+// This code is licensed under MIT license (see LICENSE for details)
+use std::fmt;
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::LtrError;
+
+/// The outcome of a single training iteration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum IterationStatus {
+    /// The training score improved (within the tolerance) and the iteration was kept.
+    Ok,
+    /// The training score did not improve and the iteration was discarded.
+    Bad,
+    /// The selected feature reached the maximum number of consecutive selections.
+    Saturated,
+    /// The iteration was kept, but the training stopped early after it.
+    Stop,
+}
+
+impl fmt::Display for IterationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IterationStatus::Ok => write!(f, "OK"),
+            IterationStatus::Bad => write!(f, "BAD"),
+            IterationStatus::Saturated => write!(f, "SATURATED"),
+            IterationStatus::Stop => write!(f, "STOP"),
+        }
+    }
+}
+
+/// Information collected on a single training iteration.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IterationRecord {
+    /// The (zero-based) iteration index.
+    pub iteration: usize,
+    /// The feature selected in the iteration.
+    pub feature: usize,
+    /// The weight assigned to the feature selected in the iteration.
+    pub weight: f32,
+    /// Training score of the model after the iteration.
+    pub training_score: f32,
+    /// Training score improvement with respect to the previous iteration.
+    pub training_improvement: f32,
+    /// Validation score of the model after the iteration, if a validation dataset is used.
+    pub validation_score: Option<f32>,
+    /// Validation score improvement with respect to the previous iteration.
+    pub validation_improvement: Option<f32>,
+    /// The outcome of the iteration.
+    pub status: IterationStatus,
+}
+
+/// The history of a training process, with one `IterationRecord` per iteration.
+///
+/// It's useful to plot learning curves and to compare training runs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TrainingHistory {
+    /// Name of the metric used on the training dataset.
+    pub metric: String,
+    /// Name of the metric used on the validation dataset.
+    pub validation_metric: String,
+    /// The (zero-based) iteration in which the final model was taken.
+    pub best_iteration: Option<usize>,
+    /// The records of each iteration.
+    pub records: Vec<IterationRecord>,
+}
+
+impl TrainingHistory {
+    /// Creates an empty `TrainingHistory`.
+    ///
+    /// # Arguments
+    /// * `metric` - Name of the metric used on the training dataset.
+    /// * `validation_metric` - Name of the metric used on the validation dataset.
+    pub fn new(metric: &str, validation_metric: &str) -> TrainingHistory {
+        TrainingHistory {
+            metric: metric.to_string(),
+            validation_metric: validation_metric.to_string(),
+            best_iteration: None,
+            records: Vec::new(),
+        }
+    }
+
+    /// Get the number of recorded iterations.
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+
+    /// Check whether no iteration was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+
+    /// Add a new record to the history.
+    ///
+    /// # Arguments
+    /// * `record` - The record of the iteration.
+    pub fn push(&mut self, record: IterationRecord) {
+        self.records.push(record);
+    }
+
+    /// Get the record of the last iteration.
+    pub fn last(&self) -> Option<&IterationRecord> {
+        self.records.last()
+    }
+
+    /// Serialize the history to JSON.
+    ///
+    /// # Errors
+    /// `LtrError::SerializationError` if the history could not be serialized.
+    pub fn to_json(&self) -> Result<String, LtrError> {
+        serde_json::to_string_pretty(self).map_err(|e| LtrError::SerializationError(e.to_string()))
+    }
+
+    /// Deserialize a history from JSON.
+    ///
+    /// # Arguments
+    /// * `json` - The JSON representation of the history.
+    ///
+    /// # Errors
+    /// `LtrError::SerializationError` if the history could not be deserialized.
+    pub fn from_json(json: &str) -> Result<TrainingHistory, LtrError> {
+        serde_json::from_str(json).map_err(|e| LtrError::SerializationError(e.to_string()))
+    }
+
+    /// Serialize the history to CSV, with a header and one line per iteration.
+    /// Validation columns are left empty when no validation dataset was used.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "iteration,feature,weight,training_score,training_improvement,\
+             validation_score,validation_improvement,status\n",
+        );
+        for record in self.records.iter() {
+            let optional = |value: Option<f32>| value.map_or(String::new(), |v| v.to_string());
+            // Writing into a `String` never fails.
+            let _ = writeln!(
+                csv,
+                "{},{},{},{},{},{},{},{}",
+                record.iteration,
+                record.feature,
+                record.weight,
+                record.training_score,
+                record.training_improvement,
+                optional(record.validation_score),
+                optional(record.validation_improvement),
+                record.status
+            );
+        }
+        csv
+    }
+
+    /// Save the history to a JSON file.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the file.
+    ///
+    /// # Errors
+    /// `LtrError` if the file could not be written.
+    pub fn save_json(&self, path: &str) -> Result<(), LtrError> {
+        std::fs::write(path, self.to_json()?).map_err(|e| LtrError::IOError(e.to_string()))
+    }
+
+    /// Save the history to a CSV file.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the file.
+    ///
+    /// # Errors
+    /// `LtrError` if the file could not be written.
+    pub fn save_csv(&self, path: &str) -> Result<(), LtrError> {
+        std::fs::write(path, self.to_csv()).map_err(|e| LtrError::IOError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history() -> TrainingHistory {
+        let mut history = TrainingHistory::new("MAP", "P@1");
+        history.push(IterationRecord {
+            iteration: 0,
+            feature: 3,
+            weight: 0.5,
+            training_score: 0.25,
+            training_improvement: 0.25,
+            validation_score: Some(0.5),
+            validation_improvement: Some(0.5),
+            status: IterationStatus::Ok,
+        });
+        history.push(IterationRecord {
+            iteration: 1,
+            feature: 3,
+            weight: 0.125,
+            training_score: 0.25,
+            training_improvement: 0.0,
+            validation_score: None,
+            validation_improvement: None,
+            status: IterationStatus::Saturated,
+        });
+        history.best_iteration = Some(0);
+        history
+    }
+
+    #[test]
+    fn test_training_history_serialization() {
+        let history = history();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history.last().unwrap().status, IterationStatus::Saturated);
+
+        let json = history.to_json().unwrap();
+        assert!(json.contains("\"status\": \"SATURATED\""));
+        assert_eq!(TrainingHistory::from_json(&json).unwrap(), history);
+
+        assert_eq!(
+            history.to_csv(),
+            "iteration,feature,weight,training_score,training_improvement,\
+             validation_score,validation_improvement,status\n\
+             0,3,0.5,0.25,0.25,0.5,0.5,OK\n\
+             1,3,0.125,0.25,0,,,SATURATED\n"
+        );
+    }
+}
@@ -1,4 +1,12 @@
-use crate::{error::LtrError, eval::Evaluator, ranker::Ranker, DataSet};
+use std::fmt;
+
+use crate::{
+    error::LtrError,
+    eval::Evaluator,
+    history::{IterationRecord, TrainingHistory},
+    ranker::Ranker,
+    DataSet,
+};
 
 /// This trait represents the basic behavior for
 /// all models implemented in the lt.rs crate.
@@ -77,8 +85,97 @@ pub trait FeaturesConfigurable {
     fn set_features(&mut self, features: Vec<usize>);
 }
 
+/// Signal returned by a `TrainingCallback` to control the training process.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallbackControl {
+    /// Keep training.
+    Continue,
+    /// Stop the training after the current iteration.
+    Stop,
+}
+
+/// The state of the training process exposed to a `TrainingCallback`
+/// after each iteration.
+pub struct IterationContext<'a> {
+    /// The record of the iteration: index, selected feature, scores and status.
+    pub record: &'a IterationRecord,
+    /// The weak ranker chosen in the iteration.
+    pub ranker: &'a dyn Ranker,
+    /// The (1-based) index of the feature the weak ranker scores with.
+    pub feature: usize,
+    /// The weights of the weak rankers in the model evaluated by the iteration, the
+    /// last one being `record.weight`. The weak ranker of an iteration that didn't
+    /// improve the model is removed after the callbacks are invoked.
+    pub ranker_weights: &'a [f32],
+    /// The weights of the training samples for the next iteration.
+    pub sample_weights: &'a [f32],
+}
+
+impl fmt::Debug for IterationContext<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IterationContext")
+            .field("record", self.record)
+            .field("feature", &self.feature)
+            .field("ranker_weights", &self.ranker_weights)
+            .field("sample_weights", &self.sample_weights)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A `TrainingCallback` observes the training process of a `Learner`.
+///
+/// It's useful for custom logging, exporting metrics or interactively
+/// stopping the training. All methods have a default no-op implementation.
+pub trait TrainingCallback {
+    /// Called once before the first training iteration.
+    fn on_fit_start(&mut self) {}
+
+    /// Called after each training iteration.
+    ///
+    /// # Arguments
+    /// * `context` - The state of the training process after the iteration.
+    ///
+    /// # Returns
+    /// `CallbackControl::Stop` to stop the training, `CallbackControl::Continue` otherwise.
+    fn on_iteration(&mut self, _context: &IterationContext) -> CallbackControl {
+        CallbackControl::Continue
+    }
+
+    /// Called once after the training process ends.
+    ///
+    /// # Arguments
+    /// * `history` - The history of the training process.
+    fn on_fit_end(&mut self, _history: &TrainingHistory) {}
+}
+
+/// `Learner`s can allow the user to observe the training process.
+/// It's not required by `Learner`, so existing implementations keep compiling.
+pub trait CallbackConfigurable {
+    /// Add a callback invoked during the training process.
+    /// Callbacks are invoked in the order they were added.
+    ///
+    /// # Arguments
+    /// * `callback` - The callback to be added.
+    fn add_callback(&mut self, callback: Box<dyn TrainingCallback>);
+}
+
 /// The `Learner`s should allow the user to save the model to a file.
 pub trait FileSerializable {
+    /// Serialize the model to a JSON string.
+    ///
+    /// # Errors
+    /// `LtrError` if the model could not be serialized.
+    fn to_json(&self) -> Result<String, LtrError>;
+
+    /// Load the model from a JSON string produced by `to_json`.
+    ///
+    /// # Arguments
+    /// * `json` - The serialized model.
+    ///
+    /// # Errors
+    /// `LtrError` if the model could not be deserialized.
+    fn load_json(&mut self, json: &str) -> Result<(), LtrError>;
+
     /// Save the model to a file.
     ///
     /// # Arguments
@@ -90,7 +187,9 @@ pub trait FileSerializable {
     /// # Errors
     /// `LtrError` if the model could not be saved.
     ///
-    fn save_to_file(&self, path: &str) -> Result<(), LtrError>;
+    fn save_to_file(&self, path: &str) -> Result<(), LtrError> {
+        std::fs::write(path, self.to_json()?).map_err(|e| LtrError::IOError(e.to_string()))
+    }
 
     /// Load the model from a file.
     ///
@@ -102,5 +201,8 @@ pub trait FileSerializable {
     ///
     /// # Errors
     /// `LtrError` if the model could not be loaded.
-    fn load_from_file(&mut self, path: &str) -> Result<(), LtrError>;
+    fn load_from_file(&mut self, path: &str) -> Result<(), LtrError> {
+        let json = std::fs::read_to_string(path).map_err(|e| LtrError::IOError(e.to_string()))?;
+        self.load_json(&json)
+    }
 }
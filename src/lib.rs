@@ -55,6 +55,10 @@ pub mod datapoint;
 /// ranking them.
 pub mod ranklist;
 
+/// Define a core primitive for the library: `DataSet`.
+/// A `DataSet` is a collection of `RankList`s with its metadata.
+pub mod dataset;
+
 /// Define the error type for the library.
 pub mod error;
 
@@ -81,9 +85,33 @@ pub mod learner;
 /// Define a class of `Ranker`s based on ensemble methods.
 pub mod ensemble;
 
+/// Define the `TrainingHistory` produced by `Learner`s, with one record
+/// per training iteration.
+pub mod history;
+
+/// Write rankings and per-query evaluations in the TREC run-file formats.
+pub mod run;
+
+/// Split a `DataSet` by query into training, validation and test sets.
+pub mod split;
+
+/// K-fold cross-validation of `Learner`s, including LETOR's pre-made folds.
+pub mod cv;
+
+/// Feature normalization (sum, z-score and linear), per query or with global statistics.
+pub mod normalize;
+
+/// Composable feature transformation pipelines, fitted on training data and shipped with a model.
+pub mod transform;
+
+/// Profile a `DataSet`: queries, documents per query, labels and feature statistics.
+pub mod profile;
+
+/// Check a `DataSet` for invalid or suspicious data before training.
+pub mod validate;
+
 /// A particular Feature for lt.rs is just a floating point value.
 /// The feature_value is the value of the feature.
 type Feature = f32;
 
-/// For simplicity, we will use a DataSet as a vector of RankLists.
-pub type DataSet = Vec<ranklist::RankList>;
+pub use dataset::{DataSet, FeatureNames};
@@ -1,4 +1,4 @@
-use crate::datapoint::DataPoint;
+use crate::datapoint::{DataPoint, FeatureStorage, QueryId};
 use crate::error::LtrError;
 use crate::ranklist::RankList;
 use crate::DataSet;
@@ -27,17 +27,39 @@ impl SVMLight {
     /// # Errors
     /// If the buffer is not in the correct format, an error is returned.
     pub fn load_datapoint(buffer: &str) -> Result<DataPoint, LtrError> {
+        SVMLight::load_datapoint_with(buffer, FeatureStorage::Dense)
+    }
+
+    /// Load a single `DataPoint` from a line of the SVMLight format, storing its
+    /// features as requested. Sparse storage keeps only the features present in the line.
+    ///
+    /// # Arguments
+    /// * `buffer` - The buffer containing the SVMLight formatted data point.
+    /// * `storage` - How the features are stored.
+    ///
+    /// # Returns
+    /// A `DataPoint` with the data loaded from the buffer.
+    ///
+    /// # Errors
+    /// If the buffer is not in the correct format, an error is returned.
+    pub fn load_datapoint_with(
+        buffer: &str,
+        storage: FeatureStorage,
+    ) -> Result<DataPoint, LtrError> {
         // Convert the buffer into a string
         let mut data_point = DataPoint::empty();
 
-        // Find # to extract the  optional description.
-        let mut buffer_iter = buffer.split('#');
-        let buffer_str = buffer_iter.next().ok_or(LtrError::ParseError(
-            "Error in SVMLight::load_datapoint: Description processing failure.",
-        ))?;
-        if let Some(info) = buffer_iter.next() {
-            data_point.set_description(info.trim());
-        }
+        // Find # to extract the  optional description and document id.
+        let buffer_str = match buffer.split_once('#') {
+            Some((data, info)) => {
+                data_point.set_description(info.trim());
+                if let Some(doc_id) = SVMLight::parse_doc_id(info) {
+                    data_point.set_doc_id(doc_id);
+                }
+                data
+            }
+            None => buffer,
+        };
 
         let mut iter = buffer_str.trim().split(' '); // Split on spaces
 
@@ -47,8 +69,10 @@ impl SVMLight {
             .ok_or(LtrError::InvalidDataPoint("Missing the label parameter."))?;
         data_point.set_label(
             label
-                .parse::<u8>()
-                .map_err(|_| LtrError::InvalidDataPoint("Invalid label parameter."))?,
+                .parse::<f32>()
+                .ok()
+                .filter(|label| label.is_finite())
+                .ok_or(LtrError::InvalidDataPoint("Invalid label parameter."))?,
         );
 
         // Get the qid:<qid>
@@ -63,14 +87,14 @@ impl SVMLight {
         let qid_str = qid_iter.next().ok_or(LtrError::ParseError(
             "Error in SVMLight::load_datapoint: Query ID processing failure",
         ))?;
-        let qid = qid_str
-            .parse::<u32>()
-            .map_err(|_| LtrError::InvalidDataPoint("Invalid qid parameter."))?;
+        if qid_str.is_empty() {
+            return Err(LtrError::InvalidDataPoint("Invalid qid parameter."));
+        }
 
-        data_point.set_query_id(qid);
+        data_point.set_query_id(qid_str);
 
         // Get the features
-        let mut feature_values = Vec::new();
+        let mut entries = Vec::new();
         for feature in iter {
             let mut feature_iter = feature.split(':');
 
@@ -78,7 +102,9 @@ impl SVMLight {
                 .next()
                 .ok_or(LtrError::InvalidDataPoint("Missing feature index."))?
                 .parse::<usize>()
-                .map_err(|_| LtrError::InvalidDataPoint("Invalid feature index."))?;
+                .ok()
+                .filter(|&index| index > 0)
+                .ok_or(LtrError::InvalidDataPoint("Invalid feature index."))?;
 
             let value = feature_iter
                 .next()
@@ -86,18 +112,44 @@ impl SVMLight {
                 .parse::<f32>()
                 .map_err(|_| LtrError::InvalidDataPoint("Invalid feature value."))?;
 
-            if index > feature_values.len() {
-                feature_values.resize(index as usize, 0.0);
-            }
-
-            feature_values[index - 1] = value;
+            entries.push((index, value));
         }
 
-        data_point.set_features(feature_values)?;
+        match storage {
+            FeatureStorage::Dense => {
+                let mut feature_values = Vec::new();
+                for (index, value) in entries {
+                    if index > feature_values.len() {
+                        feature_values.resize(index, 0.0);
+                    }
+                    feature_values[index - 1] = value;
+                }
+                data_point.set_features(feature_values)?;
+            }
+            FeatureStorage::Sparse => data_point.set_sparse_features(entries)?,
+        }
 
         Ok(data_point)
     }
 
+    /// Extract the document id from the comment of a line, written as `docid = <id>`
+    /// (as in the LETOR datasets) or `docid:<id>`.
+    ///
+    /// # Arguments
+    /// * `info` - The comment after the `#`.
+    ///
+    /// # Returns
+    /// The document id, or `None` if the comment has no `docid` key.
+    pub fn parse_doc_id(info: &str) -> Option<&str> {
+        let (_, rest) = info.split_once("docid")?;
+        let rest = rest.trim_start();
+        let rest = rest
+            .strip_prefix('=')
+            .or_else(|| rest.strip_prefix(':'))?
+            .trim_start();
+        rest.split_whitespace().next()
+    }
+
     /// Load a RankList from a SVM-Light buffer.
     /// Notice that this method DOES NOT check whether the RankList has
     /// different query ids. If you're not sure, use the `load_dataset` method.
@@ -117,14 +169,29 @@ impl SVMLight {
     /// # Errors
     /// If the buffer is not in the correct format, an error is returned.
     pub fn load_ranklist(buffer: &str) -> Result<RankList, LtrError> {
+        SVMLight::load_ranklist_with(buffer, FeatureStorage::Dense)
+    }
+
+    /// Load a RankList from a SVM-Light buffer, storing the features as requested.
+    /// See `load_ranklist`.
+    ///
+    /// # Arguments
+    /// * `buffer` - The buffer containing the SVMLight formatted rank list.
+    /// * `storage` - How the features are stored.
+    ///
+    /// # Returns
+    /// A `RankList` with the data loaded from the buffer.
+    ///
+    /// # Errors
+    /// If the buffer is not in the correct format, an error is returned.
+    pub fn load_ranklist_with(buffer: &str, storage: FeatureStorage) -> Result<RankList, LtrError> {
         let mut data_points = Vec::new();
-        let mut buffer_iter = buffer.split('\n');
-        while let Some(line) = buffer_iter.next() {
+        for line in buffer.split('\n') {
             if line.is_empty() {
                 continue;
             }
 
-            let data_point = SVMLight::load_datapoint(line)?;
+            let data_point = SVMLight::load_datapoint_with(line, storage)?;
             data_points.push(data_point);
         }
 
@@ -139,28 +206,47 @@ impl SVMLight {
     /// # Returns
     /// A `DataSet` with the data loaded from the buffer.
     pub fn load_dataset(buffer: &str) -> Result<DataSet, LtrError> {
-        let mut buffer_iter = buffer.split('\n');
+        SVMLight::load_dataset_with(buffer, FeatureStorage::Dense)
+    }
+
+    /// Load a DataSet from a SVM-Light buffer, storing the features as requested.
+    ///
+    /// # Arguments
+    /// * `buffer` - The buffer containing the SVMLight formatted dataset.
+    /// * `storage` - How the features are stored.
+    ///
+    /// # Returns
+    /// A `DataSet` with the data loaded from the buffer.
+    pub fn load_dataset_with(buffer: &str, storage: FeatureStorage) -> Result<DataSet, LtrError> {
         let mut dataset: DataSet = DataSet::new();
 
-        let mut current_query_id = 0;
+        let mut current_query_id: Option<QueryId> = None;
         let mut current_rank_list = Vec::new();
 
-        while let Some(line) = buffer_iter.next() {
+        for line in buffer.split('\n') {
             if line.is_empty() {
                 continue;
             }
-            let dp = SVMLight::load_datapoint(line)?;
-            if dp.get_query_id() == current_query_id || current_query_id == 0 {
-                current_query_id = dp.get_query_id();
-                current_rank_list.push(dp);
-            } else {
-                // Different query id, so we need to add the current rank list to the dataset
-                let ranklist = RankList::new(current_rank_list.clone());
-                dataset.push(ranklist);
-                current_rank_list.clear();
-
-                current_query_id = dp.get_query_id();
-                current_rank_list.push(dp);
+            let mut dp = SVMLight::load_datapoint_with(line, storage)?;
+            match &current_query_id {
+                // Same query: share the identifier of the first DataPoint.
+                Some(query_id) if query_id == dp.get_query_id() => {
+                    dp.set_query_id(query_id.clone());
+                    current_rank_list.push(dp);
+                }
+                None => {
+                    current_query_id = Some(dp.get_query_id().clone());
+                    current_rank_list.push(dp);
+                }
+                Some(_) => {
+                    // Different query id, so we need to add the current rank list to the dataset
+                    let ranklist = RankList::new(current_rank_list.clone());
+                    dataset.push(ranklist);
+                    current_rank_list.clear();
+
+                    current_query_id = Some(dp.get_query_id().clone());
+                    current_rank_list.push(dp);
+                }
             }
         }
         // Add the last rank list
@@ -171,14 +257,31 @@ impl SVMLight {
     }
 }
 
-impl LtrFormat for SVMLight {
-    fn load(path: &str) -> Result<DataSet, LtrError> {
+impl SVMLight {
+    /// Load a DataSet from a SVM-Light file, storing the features as requested.
+    /// Sparse storage is useful for datasets with high feature indices.
+    ///
+    /// # Arguments
+    /// * `path` - The path to the file.
+    /// * `storage` - How the features are stored.
+    ///
+    /// # Returns
+    /// A `DataSet` with the data loaded from the file.
+    pub fn load_with(path: &str, storage: FeatureStorage) -> Result<DataSet, LtrError> {
         let buffer = match std::fs::read_to_string(path) {
             Ok(buffer) => buffer,
             Err(e) => return Err(LtrError::IOError(e.to_string())),
         };
 
-        SVMLight::load_dataset(&buffer)
+        let mut dataset = SVMLight::load_dataset_with(&buffer, storage)?;
+        dataset.set_source(path);
+        Ok(dataset)
+    }
+}
+
+impl LtrFormat for SVMLight {
+    fn load(path: &str) -> Result<DataSet, LtrError> {
+        SVMLight::load_with(path, FeatureStorage::Dense)
     }
 
     fn save(_path: &str, _dataset: &DataSet) -> Result<(), LtrError> {
@@ -195,8 +298,8 @@ mod tests {
         let buffer = "1 qid:10 1:21.00 2:2.30 3:4.50 # desc";
         let data_point = SVMLight::load_datapoint(buffer).unwrap();
 
-        assert_eq!(data_point.get_label(), 1);
-        assert_eq!(data_point.get_query_id(), 10);
+        assert_eq!(data_point.get_label(), 1.0);
+        assert_eq!(data_point.get_query_id(), "10");
         assert_eq!(data_point.get_description(), Some(&"desc".to_string()));
         assert_eq!(data_point.get_features().len(), 3);
         assert_eq!(*data_point.get_feature(1).unwrap(), 21.0f32);
@@ -206,11 +309,65 @@ mod tests {
         let buffer_without_description: &str = "20 qid:9 1:1.00 2:222.30 3:444.50";
         let data_point = SVMLight::load_datapoint(buffer_without_description).unwrap();
         assert_eq!(data_point.get_description(), None);
-        assert_eq!(data_point.get_label(), 20);
-        assert_eq!(data_point.get_query_id(), 9);
+        assert_eq!(data_point.get_label(), 20.0);
+        assert_eq!(data_point.get_query_id(), "9");
         assert_eq!(data_point.get_features().len(), 3);
         assert_eq!(*data_point.get_feature(1).unwrap(), 1.0f32);
         assert_eq!(*data_point.get_feature(2).unwrap(), 222.3f32);
         assert_eq!(*data_point.get_feature(3).unwrap(), 444.5f32)
     }
+
+    #[test]
+    fn test_svm_light_sparse() {
+        let buffer = "1 qid:10 3:0.5 100000:2.0 1:1.5 # desc";
+        let sparse = SVMLight::load_datapoint_with(buffer, FeatureStorage::Sparse).unwrap();
+        let dense = SVMLight::load_datapoint(buffer).unwrap();
+
+        assert!(sparse.features().is_sparse());
+        assert_eq!(sparse.features().iter().count(), 3);
+        assert_eq!(sparse.features().len(), 100000);
+        assert_eq!(sparse.features(), dense.features());
+        assert_eq!(*sparse.get_feature(2).unwrap(), 0.0);
+        assert_eq!(*sparse.get_feature(100000).unwrap(), 2.0);
+
+        assert!(SVMLight::load_datapoint("1 qid:10 0:0.5").is_err());
+
+        let dataset = SVMLight::load_dataset_with(
+            "1 qid:1 2:0.5\n0 qid:1 9:0.5\n1 qid:2 1:1.0",
+            FeatureStorage::Sparse,
+        )
+        .unwrap();
+        assert_eq!(dataset.len(), 2);
+        assert!(dataset[1].get(0).unwrap().features().is_sparse());
+    }
+
+    #[test]
+    fn test_svm_light_identifiers() {
+        let buffer = "2 qid:q-17 1:0.5 #docid = GX008-86-4444840 inc = 1 prob = 0.086622";
+        let data_point = SVMLight::load_datapoint(buffer).unwrap();
+        assert_eq!(data_point.get_query_id(), "q-17");
+        assert_eq!(data_point.get_doc_id(), Some("GX008-86-4444840"));
+        assert_eq!(
+            data_point.get_description().unwrap(),
+            "docid = GX008-86-4444840 inc = 1 prob = 0.086622"
+        );
+
+        assert_eq!(SVMLight::parse_doc_id("docid:d1"), Some("d1"));
+        assert_eq!(SVMLight::parse_doc_id("a free text"), None);
+        assert!(SVMLight::load_datapoint("1 qid: 1:0.5").is_err());
+
+        let dataset =
+            SVMLight::load_dataset("1 qid:a 1:0.5\n0 qid:a 1:0.1\n1 qid:b 1:1.0").unwrap();
+        assert_eq!(dataset.len(), 2);
+        assert_eq!(dataset[1].get(0).unwrap().get_query_id(), "b");
+    }
+
+    #[test]
+    fn test_svm_light_real_valued_labels() {
+        let data_point = SVMLight::load_datapoint("0.35 qid:1 1:0.5").unwrap();
+        assert_eq!(data_point.get_label(), 0.35);
+        let data_point = SVMLight::load_datapoint("-1 qid:1 1:0.5").unwrap();
+        assert_eq!(data_point.get_label(), -1.0);
+        assert!(SVMLight::load_datapoint("nan qid:1 1:0.5").is_err());
+    }
 }
@@ -0,0 +1,401 @@
+/// Copyright (c) 2021 Marcos Pontes
+// This code is licensed under MIT license (see LICENSE for details)
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    datapoint::{DataPoint, Features},
+    error::LtrError,
+    learner::FileSerializable,
+    ranklist::RankList,
+    DataSet, Feature,
+};
+
+/// How the values of a feature are normalized, as RankLib's `-norm` option.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NormalizationMethod {
+    /// Divide by the sum of the absolute values.
+    Sum,
+    /// Subtract the mean and divide by the standard deviation.
+    ZScore,
+    /// Rescale linearly to `[0, 1]` using the minimum and maximum values.
+    Linear,
+}
+
+/// Which documents the normalization statistics are computed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NormalizationScope {
+    /// The statistics are computed on the documents of each query. Nothing is fitted.
+    PerQuery,
+    /// The statistics are fitted once on a `DataSet` and applied to any document.
+    Global,
+}
+
+/// Normalization statistics of a feature: a value `x` is normalized to `(x - shift) / scale`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FeatureStats {
+    /// Value subtracted from the feature.
+    pub shift: Feature,
+    /// Value the shifted feature is divided by. Never zero.
+    pub scale: Feature,
+}
+
+impl FeatureStats {
+    /// Normalize a value of the feature.
+    pub fn apply(&self, value: Feature) -> Feature {
+        (value - self.shift) / self.scale
+    }
+
+    /// Compute the statistics of a feature given its values. Non-finite values are ignored
+    /// and a zero scale (e.g. a constant feature) is replaced by 1.
+    fn fit(method: NormalizationMethod, values: impl Iterator<Item = Feature>) -> FeatureStats {
+        let values: Vec<Feature> = values.filter(|value| value.is_finite()).collect();
+        let (shift, scale) = match method {
+            NormalizationMethod::Sum => (0.0, values.iter().map(|v| v.abs()).sum()),
+            NormalizationMethod::ZScore => {
+                let n = values.len().max(1) as Feature;
+                let mean = values.iter().sum::<Feature>() / n;
+                let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<Feature>() / n;
+                (mean, variance.sqrt())
+            }
+            NormalizationMethod::Linear => {
+                let min = values.iter().copied().fold(Feature::INFINITY, Feature::min);
+                let max = values
+                    .iter()
+                    .copied()
+                    .fold(Feature::NEG_INFINITY, Feature::max);
+                match values.is_empty() {
+                    true => (0.0, 1.0),
+                    false => (min, max - min),
+                }
+            }
+        };
+        FeatureStats {
+            shift,
+            scale: if scale > 0.0 { scale } else { 1.0 },
+        }
+    }
+}
+
+/// Normalize the features of the documents of a `DataSet`.
+///
+/// A `Global` normalizer must be fitted on a (training) `DataSet` first. Its statistics
+/// can be saved to a file or attached to a model, so the same transform is applied at
+/// inference time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Normalizer {
+    /// The normalization method.
+    method: NormalizationMethod,
+    /// Which documents the statistics are computed on.
+    scope: NormalizationScope,
+    /// Fitted statistics of each feature, the feature `i` being at the position `i - 1`.
+    stats: Option<Vec<FeatureStats>>,
+}
+
+impl Normalizer {
+    /// Creates a new `Normalizer` instance.
+    ///
+    /// # Arguments
+    /// * `method` - The normalization method.
+    /// * `scope` - Which documents the statistics are computed on.
+    pub fn new(method: NormalizationMethod, scope: NormalizationScope) -> Normalizer {
+        Normalizer {
+            method,
+            scope,
+            stats: None,
+        }
+    }
+
+    /// Get the normalization method.
+    pub fn method(&self) -> NormalizationMethod {
+        self.method
+    }
+
+    /// Get which documents the statistics are computed on.
+    pub fn scope(&self) -> NormalizationScope {
+        self.scope
+    }
+
+    /// Get the fitted statistics of each feature, the feature `i` being at the position `i - 1`.
+    pub fn stats(&self) -> Option<&[FeatureStats]> {
+        self.stats.as_deref()
+    }
+
+    /// Check whether the normalizer can be applied to single documents.
+    pub fn is_fitted(&self) -> bool {
+        self.scope == NormalizationScope::Global && self.stats.is_some()
+    }
+
+    /// Fit the global statistics of each feature on a `DataSet`.
+    /// Per-query normalizers don't need to be fitted.
+    ///
+    /// # Arguments
+    /// * `dataset` - The `DataSet` the statistics are computed on.
+    pub fn fit(&mut self, dataset: &DataSet) {
+        if self.scope == NormalizationScope::Global {
+            let ranklists: Vec<_> = dataset.iter().map(|rl| rl.data_points()).collect();
+            let data_points: Vec<&DataPoint> =
+                ranklists.iter().flat_map(|dps| dps.iter()).collect();
+            self.stats = Some(self.compute_stats(&data_points, dataset.num_features()));
+        }
+    }
+
+    /// Normalize the documents of a `DataSet` in place.
+    ///
+    /// # Arguments
+    /// * `dataset` - The `DataSet` to be normalized.
+    ///
+    /// # Errors
+    /// `LtrError::ConfigurationError` if a global normalizer was not fitted.
+    pub fn transform(&self, dataset: &DataSet) -> Result<(), LtrError> {
+        let num_features = dataset.num_features();
+        for ranklist in dataset.iter() {
+            self.transform_ranklist(ranklist, num_features)?;
+        }
+        Ok(())
+    }
+
+    /// Fit the normalizer on a `DataSet` and normalize it in place.
+    ///
+    /// # Arguments
+    /// * `dataset` - The `DataSet` to be normalized.
+    pub fn fit_transform(&mut self, dataset: &DataSet) -> Result<(), LtrError> {
+        self.fit(dataset);
+        self.transform(dataset)
+    }
+
+    /// Normalize a single document with the fitted global statistics.
+    ///
+    /// # Arguments
+    /// * `data_point` - The document to be normalized.
+    ///
+    /// # Errors
+    /// `LtrError::ConfigurationError` if the normalizer is per-query or was not fitted.
+    pub fn transform_data_point(&self, data_point: &mut DataPoint) -> Result<(), LtrError> {
+        let stats = self.fitted_stats()?;
+        Normalizer::apply_stats(data_point, stats)
+    }
+
+    /// Normalize a raw slice of features with the fitted global statistics.
+    ///
+    /// # Arguments
+    /// * `features` - The features, the feature `i` being at the position `i - 1`.
+    ///
+    /// # Errors
+    /// `LtrError::ConfigurationError` if the normalizer is per-query or was not fitted.
+    pub fn transform_features(&self, features: &mut [Feature]) -> Result<(), LtrError> {
+        let stats = self.fitted_stats()?;
+        for (value, stats) in features.iter_mut().zip(stats.iter()) {
+            *value = stats.apply(*value);
+        }
+        Ok(())
+    }
+
+    /// Normalize a single feature value with the fitted global statistics.
+    /// The value is returned unchanged if the feature has no fitted statistics.
+    ///
+    /// # Arguments
+    /// * `index` - The (1-based) index of the feature.
+    /// * `value` - The raw value of the feature.
+    pub fn normalize_value(&self, index: usize, value: Feature) -> Feature {
+        match self
+            .stats
+            .as_ref()
+            .and_then(|stats| stats.get(index.wrapping_sub(1)))
+        {
+            Some(stats) if self.scope == NormalizationScope::Global => stats.apply(value),
+            _ => value,
+        }
+    }
+
+    fn fitted_stats(&self) -> Result<&[FeatureStats], LtrError> {
+        match (self.scope, self.stats.as_deref()) {
+            (NormalizationScope::Global, Some(stats)) => Ok(stats),
+            (NormalizationScope::Global, None) => Err(LtrError::ConfigurationError(
+                "The normalizer must be fitted first.",
+            )),
+            (NormalizationScope::PerQuery, _) => Err(LtrError::ConfigurationError(
+                "A per-query normalizer can only be applied to whole queries.",
+            )),
+        }
+    }
+
+    fn transform_ranklist(&self, ranklist: &RankList, num_features: usize) -> Result<(), LtrError> {
+        let per_query;
+        let stats = match self.scope {
+            NormalizationScope::Global => self.fitted_stats()?,
+            NormalizationScope::PerQuery => {
+                let data_points = ranklist.data_points();
+                let data_points: Vec<&DataPoint> = data_points.iter().collect();
+                per_query = self.compute_stats(&data_points, num_features);
+                per_query.as_slice()
+            }
+        };
+        for i in 0..ranklist.len() {
+            let mut data_point = ranklist.get(i)?.clone();
+            Normalizer::apply_stats(&mut data_point, stats)?;
+            ranklist.set(i, data_point)?;
+        }
+        Ok(())
+    }
+
+    fn compute_stats(&self, data_points: &[&DataPoint], num_features: usize) -> Vec<FeatureStats> {
+        (1..=num_features)
+            .map(|index| {
+                let values = data_points
+                    .iter()
+                    .map(|dp| dp.get_feature(index).copied().unwrap_or(0.0));
+                FeatureStats::fit(self.method, values)
+            })
+            .collect()
+    }
+
+    fn apply_stats(data_point: &mut DataPoint, stats: &[FeatureStats]) -> Result<(), LtrError> {
+        // The features missing at the end of the row are zero, as when fitting the statistics.
+        if data_point.features().len() < stats.len() {
+            match data_point.features() {
+                Features::Dense(values) => {
+                    let mut values = values.clone();
+                    values.resize(stats.len(), 0.0);
+                    data_point.set_features(values)?;
+                }
+                Features::Sparse(entries) => {
+                    let mut entries = entries.clone();
+                    entries.push((stats.len(), 0.0));
+                    data_point.set_sparse_features(entries)?;
+                }
+            }
+        }
+        for (index, stats) in stats.iter().enumerate() {
+            let value = *data_point.get_feature(index + 1)?;
+            // Keep the sparse features sparse when zero is a fixed point.
+            if value == 0.0 && stats.shift == 0.0 {
+                continue;
+            }
+            data_point.set_feature(index + 1, stats.apply(value))?;
+        }
+        Ok(())
+    }
+}
+
+impl FileSerializable for Normalizer {
+    fn to_json(&self) -> Result<String, LtrError> {
+        serde_json::to_string_pretty(self).map_err(|e| LtrError::SerializationError(e.to_string()))
+    }
+
+    fn load_json(&mut self, json: &str) -> Result<(), LtrError> {
+        *self =
+            serde_json::from_str(json).map_err(|e| LtrError::SerializationError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for Normalizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let method = match self.method {
+            NormalizationMethod::Sum => "sum",
+            NormalizationMethod::ZScore => "zscore",
+            NormalizationMethod::Linear => "linear",
+        };
+        let scope = match self.scope {
+            NormalizationScope::PerQuery => "per-query",
+            NormalizationScope::Global => "global",
+        };
+        write!(f, "{} ({})", method, scope)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rl;
+
+    use approx::relative_eq;
+
+    fn dataset() -> DataSet {
+        DataSet::from(vec![
+            rl!((1, "q1", vec![1.0, 10.0]), (0, "q1", vec![3.0, 10.0])),
+            rl!((1, "q2", vec![2.0, 20.0]), (0, "q2", vec![6.0, 20.0])),
+        ])
+    }
+
+    fn features(dataset: &DataSet, query: usize, doc: usize) -> Vec<Feature> {
+        dataset[query].get(doc).unwrap().features().to_dense()
+    }
+
+    #[test]
+    fn test_per_query_normalization() {
+        let dataset = dataset();
+        let sum = Normalizer::new(NormalizationMethod::Sum, NormalizationScope::PerQuery);
+        sum.transform(&dataset).unwrap();
+        assert_eq!(features(&dataset, 0, 0), vec![0.25, 0.5]);
+        assert_eq!(features(&dataset, 1, 1), vec![0.75, 0.5]);
+
+        let dataset = self::dataset();
+        let linear = Normalizer::new(NormalizationMethod::Linear, NormalizationScope::PerQuery);
+        linear.transform(&dataset).unwrap();
+        // Constant features are only shifted.
+        assert_eq!(features(&dataset, 0, 0), vec![0.0, 0.0]);
+        assert_eq!(features(&dataset, 1, 1), vec![1.0, 0.0]);
+
+        let mut data_point = dataset[0].get(0).unwrap().clone();
+        assert!(linear.transform_data_point(&mut data_point).is_err());
+    }
+
+    #[test]
+    fn test_short_rows() {
+        // Missing trailing features are normalized as zeros, in dense and sparse rows.
+        let mut zscore = Normalizer::new(NormalizationMethod::ZScore, NormalizationScope::Global);
+        zscore.fit(&dataset());
+        let mut sparse = DataPoint::new(0, "q3", vec![], None);
+        sparse.set_sparse_features(vec![(1, 3.0)]).unwrap();
+        let test = DataSet::from(vec![RankList::new(vec![
+            DataPoint::new(0, "q3", vec![3.0], None),
+            sparse,
+        ])]);
+        zscore.transform(&test).unwrap();
+        let expected = vec![0.0, zscore.normalize_value(2, 0.0)];
+        assert_eq!(features(&test, 0, 0), expected);
+        assert_eq!(features(&test, 0, 1), expected);
+        assert!(test[0].get(1).unwrap().features().is_sparse());
+    }
+
+    #[test]
+    fn test_global_normalization() {
+        let train = dataset();
+        let mut zscore = Normalizer::new(NormalizationMethod::ZScore, NormalizationScope::Global);
+        assert!(zscore.transform(&train).is_err());
+        zscore.fit_transform(&train).unwrap();
+        assert!(zscore.is_fitted());
+
+        let stats = zscore.stats().unwrap();
+        assert_eq!(stats[0].shift, 3.0);
+        assert!(relative_eq!(
+            stats[0].scale,
+            1.8708,
+            max_relative = 0.001f32
+        ));
+        assert_eq!(features(&train, 0, 0)[1], -1.0);
+        assert_eq!(features(&train, 1, 0)[1], 1.0);
+
+        // The fitted statistics apply to unseen documents.
+        let mut data_point = DataPoint::new(0, "q3", vec![3.0, 15.0], None);
+        zscore.transform_data_point(&mut data_point).unwrap();
+        assert_eq!(data_point.features().to_dense(), vec![0.0, 0.0]);
+        let mut raw = [3.0, 25.0, 7.0];
+        zscore.transform_features(&mut raw).unwrap();
+        assert_eq!(raw, [0.0, 2.0, 7.0]);
+        assert_eq!(zscore.normalize_value(2, 25.0), 2.0);
+        assert_eq!(zscore.normalize_value(3, 7.0), 7.0);
+
+        let path = std::env::temp_dir().join("adarank_test_normalizer.json");
+        let path = path.to_str().unwrap();
+        zscore.save_to_file(path).unwrap();
+        let mut loaded = Normalizer::new(NormalizationMethod::Sum, NormalizationScope::PerQuery);
+        loaded.load_from_file(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(loaded, zscore);
+        assert_eq!(format!("{}", loaded), "zscore (global)");
+    }
+}
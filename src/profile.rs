@@ -0,0 +1,441 @@
+/// Copyright (c) 2021 Marcos Pontes
+// This code is licensed under MIT license (see LICENSE for details)
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::LtrError,
+    eval::is_relevant,
+    utils::prettytable::{Alignment, Table, TableConfig},
+    DataSet, Feature,
+};
+
+/// Number of documents with a given relevance label.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LabelCount {
+    /// The relevance label.
+    pub label: f32,
+    /// The number of documents with the label.
+    pub count: usize,
+}
+
+/// Statistics of a feature over all the documents of a `DataSet`.
+/// Features missing in a document count as zero; NaN and infinite values are ignored.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeatureProfile {
+    /// The (1-based) index of the feature.
+    pub index: usize,
+    /// The name of the feature, if known.
+    pub name: Option<String>,
+    /// Lowest value.
+    pub min: Feature,
+    /// Highest value.
+    pub max: Feature,
+    /// Mean value.
+    pub mean: Feature,
+    /// Standard deviation.
+    pub std: Feature,
+    /// Fraction of the documents in which the feature is zero.
+    pub zero_rate: f32,
+    /// Number of NaN or infinite values.
+    pub non_finite: usize,
+    /// Whether the feature has the same value in every document.
+    pub constant: bool,
+}
+
+/// Profile of a `DataSet`: queries, documents per query, labels and features.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataSetProfile {
+    /// Number of queries.
+    pub num_queries: usize,
+    /// Number of documents.
+    pub num_documents: usize,
+    /// Lowest number of documents of a query.
+    pub min_documents: usize,
+    /// Mean number of documents per query.
+    pub mean_documents: f32,
+    /// Highest number of documents of a query.
+    pub max_documents: usize,
+    /// Number of documents per label, in increasing label order.
+    pub labels: Vec<LabelCount>,
+    /// Queries without any relevant document. Empty lists are identified by their position.
+    pub queries_without_relevant: Vec<String>,
+    /// Statistics of each feature.
+    pub features: Vec<FeatureProfile>,
+}
+
+impl DataSetProfile {
+    /// Profile a `DataSet`.
+    ///
+    /// # Arguments
+    /// * `dataset` - The `DataSet` to be profiled.
+    pub fn new(dataset: &DataSet) -> DataSetProfile {
+        let num_features = dataset.num_features();
+        let mut label_counts: BTreeMap<u32, usize> = BTreeMap::new();
+        let mut queries_without_relevant = Vec::new();
+        let mut sizes = Vec::with_capacity(dataset.len());
+        let mut accumulators = vec![FeatureAccumulator::default(); num_features];
+
+        for (position, ranklist) in dataset.iter().enumerate() {
+            let data_points = ranklist.data_points();
+            sizes.push(data_points.len());
+            if !data_points.iter().any(|dp| is_relevant(dp.get_label())) {
+                queries_without_relevant.push(
+                    ranklist
+                        .query_id()
+                        .map_or_else(|| position.to_string(), |id| id.to_string()),
+                );
+            }
+            for dp in data_points.iter() {
+                *label_counts.entry(dp.get_label().to_bits()).or_insert(0) += 1;
+                // Only the stored entries are visited; missing features are added as zeros below.
+                for (index, value) in dp.features().iter() {
+                    accumulators[index - 1].add(value);
+                }
+            }
+        }
+        let mut labels: Vec<LabelCount> = label_counts
+            .into_iter()
+            .map(|(bits, count)| LabelCount {
+                label: f32::from_bits(bits),
+                count,
+            })
+            .collect();
+        labels.sort_by(|a, b| a.label.total_cmp(&b.label));
+
+        let num_documents = sizes.iter().sum();
+        for accumulator in accumulators.iter_mut() {
+            accumulator.add_zeros(num_documents - accumulator.total());
+        }
+        let features = accumulators
+            .iter()
+            .enumerate()
+            .map(|(i, accumulator)| {
+                let name = dataset
+                    .feature_names()
+                    .and_then(|names| names.name(i + 1))
+                    .map(|name| name.to_string());
+                accumulator.profile(i + 1, name)
+            })
+            .collect();
+
+        DataSetProfile {
+            num_queries: dataset.len(),
+            num_documents,
+            min_documents: sizes.iter().copied().min().unwrap_or(0),
+            mean_documents: match dataset.len() {
+                0 => 0.0,
+                n => num_documents as f32 / n as f32,
+            },
+            max_documents: sizes.iter().copied().max().unwrap_or(0),
+            labels,
+            queries_without_relevant,
+            features,
+        }
+    }
+
+    /// Get the indices of the features with the same value in every document.
+    pub fn constant_features(&self) -> Vec<usize> {
+        self.features
+            .iter()
+            .filter(|feature| feature.constant)
+            .map(|feature| feature.index)
+            .collect()
+    }
+
+    /// Serialize the profile to JSON.
+    ///
+    /// # Errors
+    /// `LtrError::SerializationError` if the profile could not be serialized.
+    pub fn to_json(&self) -> Result<String, LtrError> {
+        serde_json::to_string_pretty(self).map_err(|e| LtrError::SerializationError(e.to_string()))
+    }
+}
+
+/// Running statistics of a feature.
+#[derive(Debug, Clone, Default)]
+struct FeatureAccumulator {
+    count: usize,
+    zeros: usize,
+    non_finite: usize,
+    sum: f64,
+    sum_squares: f64,
+    min: Option<Feature>,
+    max: Option<Feature>,
+}
+
+impl FeatureAccumulator {
+    fn add(&mut self, value: Feature) {
+        if !value.is_finite() {
+            self.non_finite += 1;
+            return;
+        }
+        self.count += 1;
+        if value == 0.0 {
+            self.zeros += 1;
+        }
+        self.sum += value as f64;
+        self.sum_squares += (value as f64).powi(2);
+        self.min = Some(self.min.map_or(value, |min| min.min(value)));
+        self.max = Some(self.max.map_or(value, |max| max.max(value)));
+    }
+
+    /// Add `n` zero values, e.g. the features missing in sparse documents.
+    fn add_zeros(&mut self, n: usize) {
+        if n == 0 {
+            return;
+        }
+        self.count += n;
+        self.zeros += n;
+        self.min = Some(self.min.map_or(0.0, |min| min.min(0.0)));
+        self.max = Some(self.max.map_or(0.0, |max| max.max(0.0)));
+    }
+
+    /// Get the number of values added, finite or not.
+    fn total(&self) -> usize {
+        self.count + self.non_finite
+    }
+
+    fn profile(&self, index: usize, name: Option<String>) -> FeatureProfile {
+        let count = self.count.max(1) as f64;
+        let mean = self.sum / count;
+        let variance = (self.sum_squares / count - mean.powi(2)).max(0.0);
+        let total = self.total();
+        FeatureProfile {
+            index,
+            name,
+            min: self.min.unwrap_or(0.0),
+            max: self.max.unwrap_or(0.0),
+            mean: mean as Feature,
+            std: variance.sqrt() as Feature,
+            zero_rate: match total {
+                0 => 0.0,
+                _ => self.zeros as f32 / total as f32,
+            },
+            non_finite: self.non_finite,
+            constant: self.min == self.max,
+        }
+    }
+}
+
+impl fmt::Display for DataSetProfile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let summary = Table::new(TableConfig::new(vec![24, 12], (1, 1), Alignment::Left));
+        let rows = [
+            ("Queries", self.num_queries.to_string()),
+            ("Documents", self.num_documents.to_string()),
+            ("Documents/query (min)", self.min_documents.to_string()),
+            (
+                "Documents/query (mean)",
+                format!("{:.2}", self.mean_documents),
+            ),
+            ("Documents/query (max)", self.max_documents.to_string()),
+            (
+                "Queries w/o relevant",
+                self.queries_without_relevant.len().to_string(),
+            ),
+            (
+                "Constant features",
+                self.constant_features().len().to_string(),
+            ),
+        ];
+        for (name, value) in rows.iter() {
+            writeln!(f, "{}", summary.render(vec![name, value.as_str()], None))?;
+        }
+
+        writeln!(f)?;
+        let labels = Table::new(TableConfig::new(vec![9, 9, 9], (2, 2), Alignment::Center));
+        writeln!(f, "{}", labels.render(vec!["Label", "Count", "Rate"], None))?;
+        for count in self.labels.iter() {
+            let rate = count.count as f32 / self.num_documents.max(1) as f32;
+            writeln!(
+                f,
+                "{}",
+                labels.render(
+                    vec![
+                        count.label.to_string().as_str(),
+                        count.count.to_string().as_str(),
+                        format!("{:.4}", rate).as_str(),
+                    ],
+                    None,
+                )
+            )?;
+        }
+
+        writeln!(f)?;
+        let name_width = self
+            .features
+            .iter()
+            .filter_map(|feature| feature.name.as_ref().map(|name| name.len()))
+            .max()
+            .unwrap_or(0)
+            .max(7);
+        let features = Table::new(TableConfig::new(
+            vec![name_width, 9, 9, 9, 9, 9, 8],
+            (1, 1),
+            Alignment::Center,
+        ));
+        write!(
+            f,
+            "{}",
+            features.render(
+                vec![
+                    "Feature",
+                    "Min",
+                    "Max",
+                    "Mean",
+                    "Std",
+                    "Zero-Rate",
+                    "Constant"
+                ],
+                None,
+            )
+        )?;
+        for feature in self.features.iter() {
+            writeln!(f)?;
+            write!(
+                f,
+                "{}",
+                features.render(
+                    vec![
+                        feature
+                            .name
+                            .clone()
+                            .unwrap_or_else(|| feature.index.to_string())
+                            .as_str(),
+                        format!("{:.4}", feature.min).as_str(),
+                        format!("{:.4}", feature.max).as_str(),
+                        format!("{:.4}", feature.mean).as_str(),
+                        format!("{:.4}", feature.std).as_str(),
+                        format!("{:.4}", feature.zero_rate).as_str(),
+                        if feature.constant { "yes" } else { "" },
+                    ],
+                    None,
+                )
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl DataSet {
+    /// Profile the `DataSet`: queries, documents per query, labels and features.
+    pub fn profile(&self) -> DataSetProfile {
+        DataSetProfile::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        datapoint::{DataPoint, FeatureStorage},
+        ranklist::RankList,
+        rl, FeatureNames,
+    };
+
+    use approx::relative_eq;
+
+    #[test]
+    fn test_profile() {
+        let mut dataset = DataSet::from(vec![
+            rl!(
+                (2, "q1", vec![1.0, 0.0, 5.0]),
+                (0, "q1", vec![3.0, 0.0, 5.0]),
+                (1, "q1", vec![f32::NAN, 0.0, 5.0])
+            ),
+            rl!((0, "q2", vec![0.0, 0.0, 5.0])),
+        ]);
+        dataset.set_feature_names(FeatureNames::parse("1 BM25-title").unwrap());
+
+        let profile = dataset.profile();
+        assert_eq!(profile.num_queries, 2);
+        assert_eq!(profile.num_documents, 4);
+        assert_eq!(profile.min_documents, 1);
+        assert_eq!(profile.mean_documents, 2.0);
+        assert_eq!(profile.max_documents, 3);
+        assert_eq!(
+            profile.labels,
+            vec![
+                LabelCount {
+                    label: 0.0,
+                    count: 2
+                },
+                LabelCount {
+                    label: 1.0,
+                    count: 1
+                },
+                LabelCount {
+                    label: 2.0,
+                    count: 1
+                },
+            ]
+        );
+        assert_eq!(profile.queries_without_relevant, vec!["q2"]);
+        assert_eq!(profile.constant_features(), vec![2, 3]);
+
+        let bm25 = &profile.features[0];
+        assert_eq!(bm25.name.as_deref(), Some("BM25-title"));
+        assert_eq!((bm25.min, bm25.max), (0.0, 3.0));
+        assert!(relative_eq!(bm25.mean, 1.3333, max_relative = 0.001f32));
+        assert!(relative_eq!(bm25.std, 1.2472, max_relative = 0.001f32));
+        assert_eq!(bm25.zero_rate, 0.25);
+        assert_eq!(bm25.non_finite, 1);
+        assert_eq!(profile.features[1].zero_rate, 1.0);
+
+        let json = profile.to_json().unwrap();
+        let parsed: DataSetProfile = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            parsed.queries_without_relevant,
+            profile.queries_without_relevant
+        );
+        assert_eq!(parsed.features.len(), 3);
+
+        let rendered = format!("{}", profile);
+        assert!(rendered.contains("BM25-title"));
+        assert!(rendered.contains("yes"));
+    }
+
+    #[test]
+    fn test_sparse_profile() {
+        let data_points = vec![
+            DataPoint::new(1.5, "q1", vec![0.0, 2.0, 0.0, -1.0], None),
+            DataPoint::new(0.5, "q1", vec![3.0], None),
+            DataPoint::new(1.5, "q2", vec![0.0, 0.0, 0.0, 4.0], None),
+        ];
+        let dense = DataSet::from(vec![RankList::new(data_points.clone())]);
+        let sparse = DataSet::from(vec![RankList::new(
+            data_points
+                .into_iter()
+                .map(|mut dp| {
+                    dp.set_feature_storage(FeatureStorage::Sparse);
+                    dp
+                })
+                .collect(),
+        )]);
+
+        let profile = sparse.profile();
+        assert_eq!(profile, dense.profile());
+        assert_eq!(
+            profile.labels,
+            vec![
+                LabelCount {
+                    label: 0.5,
+                    count: 1
+                },
+                LabelCount {
+                    label: 1.5,
+                    count: 2
+                },
+            ]
+        );
+        assert_eq!(profile.features[0].zero_rate, 2.0 / 3.0);
+        assert_eq!(
+            (profile.features[3].min, profile.features[3].max),
+            (-1.0, 4.0)
+        );
+        assert_eq!(profile.constant_features(), vec![3]);
+    }
+}
@@ -1,5 +1,191 @@
 /// Copyright (c) 2021 Marcos Pontes
-use crate::{datapoint::DataPoint, ranklist::RankList, DataSet};
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{datapoint::DataPoint, error::LtrError, ranklist::RankList, DataSet};
+
+/// How NaN scores are handled when ranking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum NanPolicy {
+    /// The ranking fails with `LtrError::NanScore`.
+    Error,
+    /// NaN scores are ranked before any other score.
+    First,
+    /// NaN scores are ranked after any other score.
+    #[default]
+    Last,
+}
+
+/// How `DataPoint`s with the same score are ordered.
+///
+/// Ties are common on single-feature weak rankers, and resolving them with the
+/// order of the input makes the metric values depend on how the data was loaded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TieBreaking {
+    /// Tied `DataPoint`s keep their original order.
+    #[default]
+    OriginalOrder,
+    /// Tied `DataPoint`s are ordered from the lowest to the highest label,
+    /// which yields the worst-case metric value.
+    WorstCase,
+    /// Tied `DataPoint`s keep their original order, but evaluators average the
+    /// metric over all the orderings of the ties (see `Evaluator::evaluate_ties`).
+    Average,
+}
+
+/// The policy applied when sorting `DataPoint`s by score.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct RankingPolicy {
+    /// How NaN scores are handled.
+    pub nan: NanPolicy,
+    /// How ties are ordered.
+    pub ties: TieBreaking,
+}
+
+impl RankingPolicy {
+    /// Creates a new `RankingPolicy`.
+    ///
+    /// # Arguments
+    /// * `nan` - How NaN scores are handled.
+    /// * `ties` - How ties are ordered.
+    pub fn new(nan: NanPolicy, ties: TieBreaking) -> RankingPolicy {
+        RankingPolicy { nan, ties }
+    }
+}
+
+/// Sort `(index, score)` pairs from the highest to the lowest score according to a policy.
+///
+/// # Arguments
+/// * `ranking` - The `(index, score)` pairs to be sorted.
+/// * `labels` - The labels of the `DataPoint`s, indexed as in `ranking`. Only used to
+///   break ties with `TieBreaking::WorstCase`.
+/// * `policy` - The ranking policy.
+///
+/// # Errors
+/// `LtrError::NanScore` if a score is NaN and the policy is `NanPolicy::Error`.
+pub fn sort_ranking(
+    ranking: &mut [(usize, f32)],
+    labels: &[f32],
+    policy: &RankingPolicy,
+) -> Result<(), LtrError> {
+    check_nan(ranking, policy)?;
+    ranking.sort_by(|a, b| compare_ranked(a, b, labels, policy));
+    Ok(())
+}
+
+/// Keep only the `k` best `(index, score)` pairs, sorted as in `sort_ranking`.
+///
+/// The best pairs are found with a partial selection, so only them are sorted. Ties
+/// are broken by the order of the indices, which matches `sort_ranking` when the pairs
+/// are in index order. With `TieBreaking::Average`, the whole group of ties at the
+/// cutoff is kept, so the ranking may be longer than `k`.
+///
+/// # Arguments
+/// * `ranking` - The `(index, score)` pairs, truncated to the `k` best.
+/// * `k` - The number of pairs to keep.
+/// * `labels` - The labels of the `DataPoint`s, indexed as in `ranking`. Only used to
+///   break ties with `TieBreaking::WorstCase`.
+/// * `policy` - The ranking policy.
+///
+/// # Errors
+/// `LtrError::NanScore` if a score is NaN and the policy is `NanPolicy::Error`.
+pub fn top_k_ranking(
+    ranking: &mut Vec<(usize, f32)>,
+    k: usize,
+    labels: &[f32],
+    policy: &RankingPolicy,
+) -> Result<(), LtrError> {
+    check_nan(ranking, policy)?;
+    let compare = |a: &(usize, f32), b: &(usize, f32)| {
+        compare_ranked(a, b, labels, policy).then(a.0.cmp(&b.0))
+    };
+
+    if k == 0 {
+        ranking.clear();
+    } else if k < ranking.len() {
+        ranking.select_nth_unstable_by(k - 1, compare);
+        let cutoff = ranking[k - 1].1;
+        let tied: Vec<(usize, f32)> = match policy.ties {
+            TieBreaking::Average => ranking[k..]
+                .iter()
+                .filter(|(_, score)| same_score(*score, cutoff))
+                .copied()
+                .collect(),
+            _ => Vec::new(),
+        };
+        ranking.truncate(k);
+        ranking.extend(tied);
+    }
+    ranking.sort_unstable_by(compare);
+    Ok(())
+}
+
+/// Fail with `LtrError::NanScore` on the first NaN score, if the policy requires it.
+fn check_nan(ranking: &[(usize, f32)], policy: &RankingPolicy) -> Result<(), LtrError> {
+    if policy.nan == NanPolicy::Error {
+        if let Some(&(i, _)) = ranking.iter().find(|(_, score)| score.is_nan()) {
+            return Err(LtrError::NanScore(i));
+        }
+    }
+    Ok(())
+}
+
+/// Order two `(index, score)` pairs from the best to the worst ranked, according to a policy.
+fn compare_ranked(
+    a: &(usize, f32),
+    b: &(usize, f32),
+    labels: &[f32],
+    policy: &RankingPolicy,
+) -> Ordering {
+    let nan_order = match policy.nan {
+        NanPolicy::First => Ordering::Less,
+        _ => Ordering::Greater,
+    };
+    let by_score = match (a.1.is_nan(), b.1.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => nan_order,
+        (false, true) => nan_order.reverse(),
+        (false, false) => b.1.partial_cmp(&a.1).unwrap_or(Ordering::Equal),
+    };
+    // Without labels (e.g. on raw feature matrices), ties keep the original order.
+    by_score.then_with(|| match policy.ties {
+        TieBreaking::WorstCase => match (labels.get(a.0), labels.get(b.0)) {
+            (Some(x), Some(y)) => x.total_cmp(y),
+            _ => Ordering::Equal,
+        },
+        _ => Ordering::Equal,
+    })
+}
+
+/// Check whether two scores are tied. NaN scores are tied with each other.
+fn same_score(a: f32, b: f32) -> bool {
+    a == b || (a.is_nan() && b.is_nan())
+}
+
+/// Get the sizes of the groups of tied scores of a sorted ranking.
+/// NaN scores are tied with each other.
+///
+/// # Arguments
+/// * `ranking` - The sorted `(index, score)` pairs.
+///
+/// # Returns
+/// The size of each group of consecutive equal scores, in order.
+pub fn tie_groups(ranking: &[(usize, f32)]) -> Vec<usize> {
+    let mut groups: Vec<usize> = Vec::new();
+    for (i, &(_, score)) in ranking.iter().enumerate() {
+        let previous = if i > 0 { Some(ranking[i - 1].1) } else { None };
+        match previous {
+            Some(p) if same_score(p, score) => {
+                if let Some(size) = groups.last_mut() {
+                    *size += 1;
+                }
+            }
+            _ => groups.push(1),
+        }
+    }
+    groups
+}
 
 /// The `Ranker` trait represents the basic behavior for
 /// the models.
@@ -24,19 +210,245 @@ pub trait Ranker {
     /// # Returns
     /// The ranked `RankList`.
     fn rank(&self, ranklist: &RankList) {
-        let mut score_per_index: Vec<(usize, f32)> = ranklist
+        let permutation = self.rank_indices(&ranklist.data_points());
+
+        // Reorder the ranklist based on the index of the sorted score
+        ranklist.permute(permutation).unwrap();
+    }
+
+    /// Generates a score for each `DataPoint` of a slice.
+    ///
+    /// # Arguments
+    /// * `data_points` - The `DataPoint`s to predict.
+    ///
+    /// # Returns
+    /// The score of each `DataPoint`, in the same order.
+    fn predict_all(&self, data_points: &[DataPoint]) -> Vec<f32> {
+        data_points.iter().map(|dp| self.predict(dp)).collect()
+    }
+
+    /// Generates a score for a raw feature vector, without building a `DataPoint`.
+    ///
+    /// Features are indexed as in `DataPoint::get_feature`: the first value of the
+    /// slice is the feature 1. The default implementation builds a `DataPoint`, so
+    /// rankers should override it to avoid the allocation.
+    ///
+    /// # Arguments
+    /// * `features` - The feature values of the candidate.
+    ///
+    /// # Returns
+    /// The score for the candidate.
+    fn predict_features(&self, features: &[f32]) -> f32 {
+        self.predict(&DataPoint::new(0, 0, features.to_vec(), None))
+    }
+
+    /// Generates a score for each row of a contiguous, row-major feature matrix.
+    ///
+    /// # Arguments
+    /// * `matrix` - The feature values of the candidates, one row after the other.
+    /// * `stride` - The number of values of each row.
+    ///
+    /// # Returns
+    /// The score of each row, in the same order.
+    ///
+    /// # Errors
+    /// `LtrError::InvalidDataPoint` if the stride is zero or the matrix length is
+    /// not a multiple of the stride.
+    fn predict_matrix(&self, matrix: &[f32], stride: usize) -> Result<Vec<f32>, LtrError> {
+        if stride == 0 || !matrix.len().is_multiple_of(stride) {
+            return Err(LtrError::InvalidDataPoint(
+                "the matrix length must be a non-zero multiple of the row stride",
+            ));
+        }
+        Ok(matrix
+            .chunks_exact(stride)
+            .map(|row| self.predict_features(row))
+            .collect())
+    }
+
+    /// Rank the rows of a contiguous, row-major feature matrix.
+    /// Since there are no labels, `TieBreaking::WorstCase` keeps the original order.
+    ///
+    /// # Arguments
+    /// * `matrix` - The feature values of the candidates, one row after the other.
+    /// * `stride` - The number of values of each row.
+    /// * `policy` - How NaN scores and ties are handled.
+    ///
+    /// # Returns
+    /// The `(row, score)` pairs from the highest to the lowest score.
+    ///
+    /// # Errors
+    /// `LtrError` if the matrix is malformed (see `predict_matrix`) or a score is NaN
+    /// and the policy is `NanPolicy::Error`.
+    fn scored_ranking_matrix(
+        &self,
+        matrix: &[f32],
+        stride: usize,
+        policy: &RankingPolicy,
+    ) -> Result<Vec<(usize, f32)>, LtrError> {
+        let mut ranking: Vec<(usize, f32)> = self
+            .predict_matrix(matrix, stride)?
+            .into_iter()
+            .enumerate()
+            .collect();
+        sort_ranking(&mut ranking, &[], policy)?;
+        Ok(ranking)
+    }
+
+    /// Get the `k` best rows of a contiguous, row-major feature matrix.
+    /// See `top_k_with` and `scored_ranking_matrix`.
+    ///
+    /// # Arguments
+    /// * `matrix` - The feature values of the candidates, one row after the other.
+    /// * `stride` - The number of values of each row.
+    /// * `k` - The number of rows to return.
+    /// * `policy` - How NaN scores and ties are handled.
+    ///
+    /// # Returns
+    /// The `(row, score)` pairs of the `k` best rows.
+    ///
+    /// # Errors
+    /// `LtrError` if the matrix is malformed (see `predict_matrix`) or a score is NaN
+    /// and the policy is `NanPolicy::Error`.
+    fn top_k_matrix(
+        &self,
+        matrix: &[f32],
+        stride: usize,
+        k: usize,
+        policy: &RankingPolicy,
+    ) -> Result<Vec<(usize, f32)>, LtrError> {
+        let mut ranking: Vec<(usize, f32)> = self
+            .predict_matrix(matrix, stride)?
             .into_iter()
             .enumerate()
-            .map(|(i, dp)| (i, self.predict(&dp)))
             .collect();
+        top_k_ranking(&mut ranking, k, &[], policy)?;
+        Ok(ranking)
+    }
 
-        // Sort by score
-        score_per_index.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+    /// Rank a slice of `DataPoint`s without reordering it.
+    /// It works on `FrozenRankList::as_slice` and `RankList::data_points`.
+    ///
+    /// # Arguments
+    /// * `data_points` - The `DataPoint`s to rank.
+    ///
+    /// # Returns
+    /// The permutation vector: the indices of the `DataPoint`s from the highest
+    /// to the lowest score. Ties keep the original order.
+    fn rank_indices(&self, data_points: &[DataPoint]) -> Vec<usize> {
+        self.scored_ranking(data_points)
+            .into_iter()
+            .map(|(i, _)| i)
+            .collect()
+    }
 
-        // Reorder the ranklist based on the index of the sorted score
-        ranklist
-            .permute(score_per_index.iter().map(|&(i, _)| i).collect())
-            .unwrap();
+    /// Rank a slice of `DataPoint`s without reordering it, keeping the scores.
+    ///
+    /// # Arguments
+    /// * `data_points` - The `DataPoint`s to rank.
+    ///
+    /// # Returns
+    /// The `(index, score)` pairs of the `DataPoint`s, from the highest to the
+    /// lowest score. Ties keep the original order.
+    /// NaN scores are ranked last.
+    fn scored_ranking(&self, data_points: &[DataPoint]) -> Vec<(usize, f32)> {
+        self.scored_ranking_with(data_points, &RankingPolicy::default())
+            .expect("the default policy never fails")
+    }
+
+    /// Rank a slice of `DataPoint`s without reordering it, keeping the scores,
+    /// following a `RankingPolicy`.
+    ///
+    /// # Arguments
+    /// * `data_points` - The `DataPoint`s to rank.
+    /// * `policy` - How NaN scores and ties are handled.
+    ///
+    /// # Returns
+    /// The `(index, score)` pairs of the `DataPoint`s, from the highest to the
+    /// lowest score.
+    ///
+    /// # Errors
+    /// `LtrError::NanScore` if a score is NaN and the policy is `NanPolicy::Error`.
+    fn scored_ranking_with(
+        &self,
+        data_points: &[DataPoint],
+        policy: &RankingPolicy,
+    ) -> Result<Vec<(usize, f32)>, LtrError> {
+        let mut ranking: Vec<(usize, f32)> = self
+            .predict_all(data_points)
+            .into_iter()
+            .enumerate()
+            .collect();
+        let labels: Vec<f32> = match policy.ties {
+            TieBreaking::WorstCase => data_points.iter().map(|dp| dp.get_label()).collect(),
+            _ => Vec::new(),
+        };
+        sort_ranking(&mut ranking, &labels, policy)?;
+        Ok(ranking)
+    }
+
+    /// Get the `k` best ranked `DataPoint`s of a slice, keeping the scores.
+    ///
+    /// Unlike `scored_ranking`, only the `k` best `DataPoint`s are sorted, which is much
+    /// cheaper on large candidate lists.
+    ///
+    /// # Arguments
+    /// * `data_points` - The `DataPoint`s to rank.
+    /// * `k` - The number of `DataPoint`s to return.
+    ///
+    /// # Returns
+    /// The `(index, score)` pairs of the `k` best `DataPoint`s, in the same order as the
+    /// first `k` pairs of `scored_ranking`.
+    fn top_k(&self, data_points: &[DataPoint], k: usize) -> Vec<(usize, f32)> {
+        self.top_k_with(data_points, k, &RankingPolicy::default())
+            .expect("the default policy never fails")
+    }
+
+    /// Get the `k` best ranked `DataPoint`s of a slice, keeping the scores,
+    /// following a `RankingPolicy`. See `top_k_ranking`.
+    ///
+    /// # Arguments
+    /// * `data_points` - The `DataPoint`s to rank.
+    /// * `k` - The number of `DataPoint`s to return.
+    /// * `policy` - How NaN scores and ties are handled.
+    ///
+    /// # Returns
+    /// The `(index, score)` pairs of the `k` best `DataPoint`s. With `TieBreaking::Average`,
+    /// the ties at the cutoff are also returned.
+    ///
+    /// # Errors
+    /// `LtrError::NanScore` if a score is NaN and the policy is `NanPolicy::Error`.
+    fn top_k_with(
+        &self,
+        data_points: &[DataPoint],
+        k: usize,
+        policy: &RankingPolicy,
+    ) -> Result<Vec<(usize, f32)>, LtrError> {
+        let mut ranking: Vec<(usize, f32)> = self
+            .predict_all(data_points)
+            .into_iter()
+            .enumerate()
+            .collect();
+        let labels: Vec<f32> = match policy.ties {
+            TieBreaking::WorstCase => data_points.iter().map(|dp| dp.get_label()).collect(),
+            _ => Vec::new(),
+        };
+        top_k_ranking(&mut ranking, k, &labels, policy)?;
+        Ok(ranking)
+    }
+
+    /// Rank each `RankList` of a `DataSet` without reordering it, keeping the scores.
+    ///
+    /// # Arguments
+    /// * `dataset` - The `DataSet` to rank.
+    ///
+    /// # Returns
+    /// The `(index, score)` pairs of each `RankList`, as in `scored_ranking`.
+    fn scored_ranking_dataset(&self, dataset: &DataSet) -> Vec<Vec<(usize, f32)>> {
+        dataset
+            .iter()
+            .map(|ranklist| self.scored_ranking(&ranklist.data_points()))
+            .collect()
     }
 
     /// Perform ranking on a `DataSet`.
@@ -49,3 +461,144 @@ pub trait Ranker {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ensemble::weak::WeakRanker, rl};
+
+    #[test]
+    fn test_scored_ranking() {
+        let dataset = DataSet::from(vec![
+            rl!(
+                (0, 1, vec![0.1, 0.5]),
+                (1, 1, vec![0.9, 0.1]),
+                (0, 1, vec![0.5, 0.9])
+            ),
+            rl!((1, 2, vec![0.2, 0.3]), (0, 2, vec![0.4, 0.3])),
+        ]);
+        let ranker = WeakRanker::new(1);
+
+        assert_eq!(
+            ranker.scored_ranking(&dataset[0].data_points()),
+            vec![(1, 0.9), (2, 0.5), (0, 0.1)]
+        );
+        assert_eq!(
+            ranker.rank_indices(&dataset[0].data_points()),
+            vec![1, 2, 0]
+        );
+        assert_eq!(
+            ranker.scored_ranking_dataset(&dataset),
+            vec![vec![(1, 0.9), (2, 0.5), (0, 0.1)], vec![(1, 0.4), (0, 0.2)]]
+        );
+
+        // Ties keep the original order.
+        assert_eq!(
+            WeakRanker::new(2).rank_indices(&dataset[1].data_points()),
+            vec![0, 1]
+        );
+
+        // The dataset was not reordered.
+        assert_eq!(dataset[0].get(0).unwrap().get_label(), 0.0);
+    }
+
+    #[test]
+    fn test_ranking_policy() {
+        let labels = vec![0.0, 1.0, 0.0, 1.0, 0.0];
+        let scores = vec![(0, 0.5), (1, f32::NAN), (2, 0.9), (3, 0.5), (4, 0.1)];
+
+        let mut ranking = scores.clone();
+        sort_ranking(&mut ranking, &labels, &RankingPolicy::default()).unwrap();
+        let order: Vec<usize> = ranking.iter().map(|&(i, _)| i).collect();
+        assert_eq!(order, vec![2, 0, 3, 4, 1]);
+        assert_eq!(tie_groups(&ranking), vec![1, 2, 1, 1]);
+
+        let mut ranking = scores.clone();
+        let policy = RankingPolicy::new(NanPolicy::First, TieBreaking::WorstCase);
+        sort_ranking(&mut ranking, &labels, &policy).unwrap();
+        let order: Vec<usize> = ranking.iter().map(|&(i, _)| i).collect();
+        assert_eq!(order, vec![1, 2, 0, 3, 4]);
+
+        let mut ranking = scores;
+        let policy = RankingPolicy::new(NanPolicy::Error, TieBreaking::OriginalOrder);
+        assert_eq!(
+            sort_ranking(&mut ranking, &labels, &policy),
+            Err(LtrError::NanScore(1))
+        );
+    }
+
+    #[test]
+    fn test_top_k() {
+        let ranklist = rl!(
+            (0, 1, vec![0.3]),
+            (1, 1, vec![0.9]),
+            (0, 1, vec![0.5]),
+            (1, 1, vec![0.5]),
+            (0, 1, vec![f32::NAN]),
+            (1, 1, vec![0.5]),
+            (0, 1, vec![0.1])
+        );
+        let data_points = ranklist.data_points();
+        let ranker = WeakRanker::new(1);
+        let full = ranker.scored_ranking(&data_points);
+
+        for k in 0..=data_points.len() + 1 {
+            let top = ranker.top_k(&data_points, k);
+            assert_eq!(top.len(), k.min(data_points.len()));
+            assert_eq!(top.iter().map(|&(i, _)| i).collect::<Vec<usize>>(), {
+                full.iter().take(k).map(|&(i, _)| i).collect::<Vec<usize>>()
+            });
+        }
+
+        let worst_case = RankingPolicy::new(NanPolicy::Last, TieBreaking::WorstCase);
+        let top = ranker.top_k_with(&data_points, 3, &worst_case).unwrap();
+        assert_eq!(
+            top.iter().map(|&(i, _)| i).collect::<Vec<usize>>(),
+            vec![1, 2, 3]
+        );
+
+        // The ties at the cutoff are kept to average over them.
+        let average = RankingPolicy::new(NanPolicy::Last, TieBreaking::Average);
+        let top = ranker.top_k_with(&data_points, 2, &average).unwrap();
+        assert_eq!(
+            top.iter().map(|&(i, _)| i).collect::<Vec<usize>>(),
+            vec![1, 2, 3, 5]
+        );
+
+        let strict = RankingPolicy::new(NanPolicy::Error, TieBreaking::OriginalOrder);
+        assert_eq!(
+            ranker.top_k_with(&data_points, 1, &strict),
+            Err(LtrError::NanScore(4))
+        );
+    }
+
+    #[test]
+    fn test_predict_matrix() {
+        let ranklist = rl!(
+            (0, 1, vec![0.3, 0.1]),
+            (1, 1, vec![0.9, 0.4]),
+            (0, 1, vec![0.5, 0.4])
+        );
+        let matrix = vec![0.3, 0.1, 0.9, 0.4, 0.5, 0.4];
+        let ranker = WeakRanker::new(2);
+
+        assert_eq!(ranker.predict_features(&matrix[2..4]), 0.4);
+        assert_eq!(ranker.predict_features(&[]), 0.0);
+        assert_eq!(
+            ranker.predict_matrix(&matrix, 2).unwrap(),
+            ranker.predict_all(&ranklist.data_points())
+        );
+        assert!(ranker.predict_matrix(&matrix, 4).is_err());
+        assert!(ranker.predict_matrix(&matrix, 0).is_err());
+
+        let policy = RankingPolicy::new(NanPolicy::Last, TieBreaking::WorstCase);
+        assert_eq!(
+            ranker.scored_ranking_matrix(&matrix, 2, &policy).unwrap(),
+            ranker.scored_ranking(&ranklist.data_points())
+        );
+        assert_eq!(
+            ranker.top_k_matrix(&matrix, 2, 1, &policy).unwrap(),
+            vec![(1, 0.4)]
+        );
+    }
+}
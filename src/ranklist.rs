@@ -1,16 +1,19 @@
 use std::cell::{Ref, RefCell};
 use std::fmt;
 use std::fmt::Formatter;
+use std::sync::Arc;
 
 use serde::{Deserialize, Serialize};
 
-use crate::datapoint::DataPoint;
+use crate::datapoint::{DataPoint, QueryId};
 use crate::error::LtrError;
+use crate::eval::is_relevant;
+use crate::ranker::{sort_ranking, RankingPolicy};
 
 /// A RankList is the object to be ranked by `Learner`s.
 ///
 /// The RankList primitive represents a collections of `DataPoint`s
-/// corresponding to the same query id. This property is checked at runtime.
+/// corresponding to the same query id. This property is checked by `DataSet::validate`.
 ///
 /// RankLists are used by `Learner`s to rank `DataPoint`s and offer a way to
 /// evaluate the performance of the `Learner`.
@@ -18,8 +21,9 @@ use crate::error::LtrError;
 /// It's important to notice that RankList offers interior mutability,
 /// which means that it's possible to modify a RankList
 /// without mutable borrowing it. This is particularly useful when
-/// shuffling the `DataPoint`s inside the RankList.
-#[derive(Clone, Serialize, Deserialize)]
+/// shuffling the `DataPoint`s inside the RankList. As a consequence, a
+/// RankList is not `Sync`: use `FrozenRankList` to share it across threads.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RankList {
     ///
     /// The list of `DataPoint`s.
@@ -39,6 +43,18 @@ impl RankList {
         }
     }
 
+    /// Get the query id of the `RankList`, i.e., the query id of its first `DataPoint`.
+    ///
+    /// # Returns
+    ///
+    /// The query id, or `None` if the `RankList` is empty.
+    pub fn query_id(&self) -> Option<QueryId> {
+        self.data_points
+            .borrow()
+            .first()
+            .map(|dp| dp.get_query_id().clone())
+    }
+
     /// Get the length of the `RankList`.
     ///
     /// # Returns
@@ -48,6 +64,28 @@ impl RankList {
         self.data_points.borrow().len()
     }
 
+    /// Check whether the `RankList` has no `DataPoint`s.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the `RankList` is empty.
+    pub fn is_empty(&self) -> bool {
+        self.data_points.borrow().is_empty()
+    }
+
+    /// Check whether the `RankList` has a relevant `DataPoint`. Without one,
+    /// evaluators such as MAP and NDCG always score the query 0.
+    ///
+    /// # Returns
+    ///
+    /// `true` if any label is relevant, as defined by `is_relevant`.
+    pub fn has_relevant(&self) -> bool {
+        self.data_points
+            .borrow()
+            .iter()
+            .any(|dp| is_relevant(dp.get_label()))
+    }
+
     /// Get the `DataPoint` at the given index.
     ///
     /// # Arguments
@@ -57,7 +95,7 @@ impl RankList {
     /// # Returns
     ///
     /// The `DataPoint` at the given index.
-    pub fn get(&self, index: usize) -> Result<Ref<DataPoint>, LtrError> {
+    pub fn get(&self, index: usize) -> Result<Ref<'_, DataPoint>, LtrError> {
         if index < self.len() {
             Ok(Ref::map(self.data_points.borrow(), |dp| &dp[index]))
         } else {
@@ -86,12 +124,14 @@ impl RankList {
     /// * `Ok(())` if the `RankList` was ranked successfully.
     ///
     /// # Errors
-    /// * `LtrError` if the `RankList` could not be ranked.
+    /// * `LtrError::InvalidDataPoint` if a label is NaN. The `RankList` is left unchanged.
     pub fn rank(&self) -> Result<(), LtrError> {
+        let mut data_points = self.data_points.borrow_mut();
+        if data_points.iter().any(|dp| dp.get_label().is_nan()) {
+            return Err(LtrError::InvalidDataPoint("NaN relevance label."));
+        }
         // Reverse sorting
-        self.data_points
-            .borrow_mut()
-            .sort_by(|a, b| b.partial_cmp(&a).unwrap());
+        data_points.sort_by(|a, b| b.get_label().total_cmp(&a.get_label()));
         Ok(())
     }
 
@@ -106,13 +146,31 @@ impl RankList {
     /// # Errors
     /// * `LtrError` if the `RankList` could not be ranked.
     pub fn rank_by_feature(&self, feature_index: usize) -> Result<(), LtrError> {
-        self.data_points.borrow_mut().sort_by(|a, b| {
-            b.get_feature(feature_index)
-                .unwrap()
-                .partial_cmp(&a.get_feature(feature_index).unwrap())
-                .unwrap()
-        });
-        Ok(())
+        self.rank_by_feature_with(feature_index, &RankingPolicy::default())
+    }
+
+    /// Rank the `RankList` by a given feature index, following a `RankingPolicy`.
+    ///
+    /// # Arguments
+    /// * `feature_index` - The index of the feature to be used to sort the `RankList`.
+    /// * `policy` - How NaN values and ties are handled.
+    ///
+    /// # Errors
+    /// * `LtrError::FeatureIndexOutOfBounds` if a `DataPoint` doesn't have the feature.
+    /// * `LtrError::NanScore` if a value is NaN and the policy is `NanPolicy::Error`.
+    pub fn rank_by_feature_with(
+        &self,
+        feature_index: usize,
+        policy: &RankingPolicy,
+    ) -> Result<(), LtrError> {
+        let mut ranking = Vec::with_capacity(self.len());
+        let mut labels = Vec::with_capacity(self.len());
+        for (i, dp) in self.data_points.borrow().iter().enumerate() {
+            ranking.push((i, *dp.get_feature(feature_index)?));
+            labels.push(dp.get_label());
+        }
+        sort_ranking(&mut ranking, &labels, policy)?;
+        self.permute(ranking.into_iter().map(|(i, _)| i).collect())
     }
 
     /// Permute the `RankList` according to the given permutation vector.
@@ -136,6 +194,150 @@ impl RankList {
         self.data_points.replace(new_data_points);
         Ok(())
     }
+
+    /// Borrow the `DataPoint`s of the `RankList` as a slice.
+    ///
+    /// # Returns
+    ///
+    /// The `DataPoint`s in their current order.
+    pub fn data_points(&self) -> Ref<'_, [DataPoint]> {
+        Ref::map(self.data_points.borrow(), |dp| dp.as_slice())
+    }
+
+    /// Take an immutable, thread-safe copy of the `RankList`.
+    ///
+    /// # Returns
+    ///
+    /// A `FrozenRankList` with the `DataPoint`s in their current order.
+    pub fn freeze(&self) -> FrozenRankList {
+        FrozenRankList::new(self.data_points.borrow().clone())
+    }
+}
+
+/// A FrozenRankList is an immutable alternative to `RankList`.
+///
+/// Unlike `RankList`, it has no interior mutability, so it is `Sync` and can be
+/// shared across threads. Its `DataPoint`s are reference counted, so cloning
+/// a FrozenRankList is cheap. Operations that would reorder the `DataPoint`s
+/// return a new FrozenRankList instead.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FrozenRankList {
+    ///
+    /// The list of `DataPoint`s.
+    ///
+    data_points: Arc<[DataPoint]>,
+}
+
+impl FrozenRankList {
+    /// Creates a new `FrozenRankList` with the given `DataPoint`s.
+    ///
+    /// # Arguments
+    ///
+    /// * `data_points` - The list of `DataPoint`s.
+    pub fn new(data_points: Vec<DataPoint>) -> FrozenRankList {
+        FrozenRankList {
+            data_points: data_points.into(),
+        }
+    }
+
+    /// Get the query id of the `FrozenRankList`, i.e., the query id of its first `DataPoint`.
+    pub fn query_id(&self) -> Option<&QueryId> {
+        self.data_points.first().map(|dp| dp.get_query_id())
+    }
+
+    /// Get the length of the `FrozenRankList`.
+    pub fn len(&self) -> usize {
+        self.data_points.len()
+    }
+
+    /// Check whether the `FrozenRankList` has no `DataPoint`s.
+    pub fn is_empty(&self) -> bool {
+        self.data_points.is_empty()
+    }
+
+    /// Get the `DataPoint` at the given index.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the `DataPoint` to be returned.
+    ///
+    /// # Returns
+    ///
+    /// The `DataPoint` at the given index.
+    pub fn get(&self, index: usize) -> Result<&DataPoint, LtrError> {
+        self.data_points
+            .get(index)
+            .ok_or(LtrError::RankListIndexOutOfBounds(index))
+    }
+
+    /// Get the `DataPoint`s as a slice.
+    pub fn as_slice(&self) -> &[DataPoint] {
+        &self.data_points
+    }
+
+    /// Iterate over the `DataPoint`s.
+    pub fn iter(&self) -> std::slice::Iter<'_, DataPoint> {
+        self.data_points.iter()
+    }
+
+    /// Get the labels of the `DataPoint`s, in order.
+    /// This is useful to evaluate the `FrozenRankList` with `Evaluator::evaluate_labels`.
+    pub fn labels(&self) -> Vec<f32> {
+        self.data_points.iter().map(|dp| dp.get_label()).collect()
+    }
+
+    /// Create a new `FrozenRankList` with the `DataPoint`s reordered by the given
+    /// permutation vector, as returned by `Ranker::rank_indices`.
+    ///
+    /// # Arguments
+    /// * `permutation` - The permutation vector.
+    ///
+    /// # Errors
+    /// * `LtrError` if the permutation has an invalid index.
+    pub fn permute(&self, permutation: &[usize]) -> Result<FrozenRankList, LtrError> {
+        let mut data_points = Vec::with_capacity(permutation.len());
+        for &i in permutation {
+            data_points.push(self.get(i)?.clone());
+        }
+        Ok(FrozenRankList::new(data_points))
+    }
+}
+
+impl<'a> IntoIterator for &'a FrozenRankList {
+    type Item = &'a DataPoint;
+    type IntoIter = std::slice::Iter<'a, DataPoint>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl From<Vec<DataPoint>> for FrozenRankList {
+    fn from(data_points: Vec<DataPoint>) -> FrozenRankList {
+        FrozenRankList::new(data_points)
+    }
+}
+
+impl From<&RankList> for FrozenRankList {
+    fn from(ranklist: &RankList) -> FrozenRankList {
+        ranklist.freeze()
+    }
+}
+
+impl From<FrozenRankList> for RankList {
+    fn from(ranklist: FrozenRankList) -> RankList {
+        RankList::new(ranklist.data_points.to_vec())
+    }
+}
+
+impl fmt::Display for FrozenRankList {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "FrozenRankList object with {} data points",
+            self.data_points.len()
+        )
+    }
 }
 
 /// A `RankList` iterator.
@@ -201,19 +403,13 @@ impl fmt::Display for RankList {
 macro_rules! rl {
     ($(($label:expr, $query_id:expr, $features:expr)),*) => {
         {
-            let mut data_points = Vec::new();
-            $(
-                data_points.push(crate::dp!($label, $query_id, $features));
-            )*
+            let data_points = vec![$($crate::dp!($label, $query_id, $features)),*];
             RankList::new(data_points)
         }
     };
     ($(($label:expr, $query_id:expr, $features:expr, $description:expr)),*) => {
         {
-            let mut data_points = Vec::new();
-            $(
-                data_points.push(crate::dp!($label, $query_id, $features, $description));
-            )*
+            let data_points = vec![$($crate::dp!($label, $query_id, $features, $description)),*];
             RankList::new(data_points)
         }
     };
@@ -222,9 +418,14 @@ macro_rules! rl {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{dp, loader::svmlight::*};
+    use crate::{
+        dp,
+        loader::svmlight::*,
+        ranker::{NanPolicy, TieBreaking},
+    };
 
     #[test]
+    #[allow(clippy::unnecessary_cast)]
     fn test_ranklist() {
         let rank_list = rl!(
             (0, 9, vec![10.0, 1.2, 4.3, 5.4], "doc1"),
@@ -244,20 +445,20 @@ mod tests {
 
         // checking the first data point just for sanity
         let first_data_point = rank_list.get(0).unwrap();
-        assert_eq!(first_data_point.get_label(), 0);
-        assert_eq!(first_data_point.get_query_id(), 9);
+        assert_eq!(first_data_point.get_label(), 0.0);
+        assert_eq!(first_data_point.get_query_id(), "9");
         assert_eq!(*first_data_point.get_feature(1).unwrap(), 10.0f32);
 
         // checking the second data point just for sanity
         let second_data_point = rank_list.get(1).unwrap();
-        assert_eq!(second_data_point.get_label(), 1);
-        assert_eq!(second_data_point.get_query_id(), 9);
+        assert_eq!(second_data_point.get_label(), 1.0);
+        assert_eq!(second_data_point.get_query_id(), "9");
         assert_eq!(*second_data_point.get_feature(2).unwrap(), 2.2f32);
 
         // checking the third data point just for sanity
         let third_data_point = rank_list.get(2).unwrap();
-        assert_eq!(third_data_point.get_label(), 0);
-        assert_eq!(third_data_point.get_query_id(), 9);
+        assert_eq!(third_data_point.get_label(), 0.0);
+        assert_eq!(third_data_point.get_query_id(), "9");
         assert_eq!(*third_data_point.get_feature(3).unwrap(), 4.7f32);
 
         let string_representation = format!("{}", rank_list);
@@ -280,6 +481,16 @@ mod tests {
             "doc1"
         );
 
+        let nan_rank_list = rank_list.clone();
+        nan_rank_list
+            .set(1, DataPoint::new(f32::NAN, 9, vec![0.0], None))
+            .unwrap();
+        assert_eq!(
+            nan_rank_list.rank(),
+            Err(LtrError::InvalidDataPoint("NaN relevance label."))
+        );
+        assert!(nan_rank_list.get(1).unwrap().get_label().is_nan());
+
         let full_rank_list = rank_list.clone();
         full_rank_list.rank().unwrap();
         assert_eq!(full_rank_list.len(), 3);
@@ -371,4 +582,63 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_frozen_ranklist() {
+        fn assert_sync<T: Send + Sync>() {}
+        assert_sync::<FrozenRankList>();
+
+        let rank_list = rl!(
+            (0, 9, vec![10.0, 1.2], "doc1"),
+            (1, 9, vec![11.0, 2.2], "doc2"),
+            (0, 9, vec![12.0, 2.5], "doc3")
+        );
+        let frozen = rank_list.freeze();
+
+        assert_eq!(frozen.len(), 3);
+        assert!(!frozen.is_empty());
+        assert_eq!(frozen.labels(), vec![0.0, 1.0, 0.0]);
+        assert_eq!(frozen.get(1).unwrap().get_description().unwrap(), "doc2");
+        assert_eq!(frozen.get(3), Err(LtrError::RankListIndexOutOfBounds(3)));
+        assert_eq!(
+            format!("{}", frozen),
+            "FrozenRankList object with 3 data points"
+        );
+
+        let permuted = frozen.permute(&[2, 0, 1]).unwrap();
+        assert_eq!(permuted.labels(), vec![0.0, 0.0, 1.0]);
+        assert_eq!(frozen.labels(), vec![0.0, 1.0, 0.0]);
+        assert!(frozen.permute(&[3]).is_err());
+
+        let thawed = RankList::from(permuted);
+        assert_eq!(thawed.get(0).unwrap().get_description().unwrap(), "doc3");
+        assert_eq!(rank_list.data_points().len(), 3);
+    }
+
+    #[test]
+    fn test_rank_by_feature_nan() {
+        let rank_list = rl!(
+            (0, 9, vec![f32::NAN, 1.0], "doc1"),
+            (1, 9, vec![0.5, 1.0], "doc2"),
+            (0, 9, vec![0.8, 1.0], "doc3")
+        );
+
+        rank_list.rank_by_feature(1).unwrap();
+        assert_eq!(rank_list.get(0).unwrap().get_description().unwrap(), "doc3");
+        assert_eq!(rank_list.get(2).unwrap().get_description().unwrap(), "doc1");
+
+        let strict = RankingPolicy::new(NanPolicy::Error, TieBreaking::OriginalOrder);
+        assert_eq!(
+            rank_list.rank_by_feature_with(1, &strict),
+            Err(LtrError::NanScore(2))
+        );
+        assert_eq!(
+            rank_list.rank_by_feature(3),
+            Err(LtrError::FeatureIndexOutOfBounds(3))
+        );
+
+        let worst_case = RankingPolicy::new(NanPolicy::Last, TieBreaking::WorstCase);
+        rank_list.rank_by_feature_with(2, &worst_case).unwrap();
+        assert_eq!(rank_list.freeze().labels(), vec![0.0, 0.0, 1.0]);
+    }
 }
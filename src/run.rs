@@ -0,0 +1,149 @@
+/// Copyright (c) 2021 Marcos Pontes
+// This code is licensed under MIT license (see LICENSE for details)
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+use crate::{
+    error::LtrError,
+    eval::QueryScore,
+    ranker::{Ranker, RankingPolicy},
+    DataSet,
+};
+
+/// Write the rankings of a `DataSet` in the TREC run-file format:
+/// `<qid> Q0 <docid> <rank> <score> <tag>`, with ranks starting at 1.
+///
+/// `DataPoint`s without a document id are identified by their position in the `RankList`.
+/// The `DataSet` is not reordered.
+///
+/// # Arguments
+/// * `writer` - The output stream.
+/// * `ranker` - The `Ranker` used to rank each query.
+/// * `dataset` - The `DataSet` to be ranked.
+/// * `policy` - How NaN scores and ties are handled.
+/// * `tag` - The name of the run.
+///
+/// # Errors
+/// `LtrError` if a ranking fails or the output could not be written.
+pub fn write_run<W: Write>(
+    writer: &mut W,
+    ranker: &dyn Ranker,
+    dataset: &DataSet,
+    policy: &RankingPolicy,
+    tag: &str,
+) -> Result<(), LtrError> {
+    for ranklist in dataset.iter() {
+        let data_points = ranklist.data_points();
+        let ranking = ranker.scored_ranking_with(&data_points, policy)?;
+        for (rank, (index, score)) in ranking.into_iter().enumerate() {
+            let data_point = &data_points[index];
+            let position = index.to_string();
+            writeln!(
+                writer,
+                "{} Q0 {} {} {} {}",
+                data_point.get_query_id(),
+                data_point.get_doc_id().unwrap_or(&position),
+                rank + 1,
+                score,
+                tag
+            )
+            .map_err(|e| LtrError::IOError(e.to_string()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Save the rankings of a `DataSet` to a TREC run file. See `write_run`.
+///
+/// # Arguments
+/// * `path` - The path of the run file.
+/// * `ranker` - The `Ranker` used to rank each query.
+/// * `dataset` - The `DataSet` to be ranked.
+/// * `policy` - How NaN scores and ties are handled.
+/// * `tag` - The name of the run.
+///
+/// # Errors
+/// `LtrError` if a ranking fails or the file could not be written.
+pub fn save_run(
+    path: &str,
+    ranker: &dyn Ranker,
+    dataset: &DataSet,
+    policy: &RankingPolicy,
+    tag: &str,
+) -> Result<(), LtrError> {
+    let file = File::create(path).map_err(|e| LtrError::IOError(e.to_string()))?;
+    let mut writer = BufWriter::new(file);
+    write_run(&mut writer, ranker, dataset, policy, tag)?;
+    writer.flush().map_err(|e| LtrError::IOError(e.to_string()))
+}
+
+/// Write per-query metric values as `trec_eval -q` does: `<metric> <qid> <score>`,
+/// followed by the mean over all the queries with `all` as query id.
+///
+/// # Arguments
+/// * `writer` - The output stream.
+/// * `metric` - The name of the metric.
+/// * `scores` - The metric value of each query, as returned by `Evaluator::evaluate_queries`.
+///
+/// # Errors
+/// `LtrError` if the output could not be written.
+pub fn write_evaluation<W: Write>(
+    writer: &mut W,
+    metric: &str,
+    scores: &[QueryScore],
+) -> Result<(), LtrError> {
+    let io_error = |e: std::io::Error| LtrError::IOError(e.to_string());
+    for query in scores.iter() {
+        writeln!(writer, "{}\t{}\t{:.4}", metric, query.query_id, query.score).map_err(io_error)?;
+    }
+    if !scores.is_empty() {
+        let mean = scores.iter().map(|query| query.score).sum::<f32>() / scores.len() as f32;
+        writeln!(writer, "{}\tall\t{:.4}", metric, mean).map_err(io_error)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ensemble::weak::WeakRanker,
+        eval::{map::MAP, Evaluator},
+        loader::svmlight::SVMLight,
+    };
+
+    #[test]
+    fn test_run_file() {
+        let dataset = SVMLight::load_dataset(
+            "0 qid:q1 1:0.1 # docid = d1\n\
+             1 qid:q1 1:0.9 # docid = d2\n\
+             1 qid:q1 1:0.5\n\
+             1 qid:q2 1:0.2 # docid = d4",
+        )
+        .unwrap();
+        let ranker = WeakRanker::new(1);
+        let policy = RankingPolicy::default();
+
+        let mut run = Vec::new();
+        write_run(&mut run, &ranker, &dataset, &policy, "weak").unwrap();
+        assert_eq!(
+            String::from_utf8(run).unwrap(),
+            "q1 Q0 d2 1 0.9 weak\n\
+             q1 Q0 2 2 0.5 weak\n\
+             q1 Q0 d1 3 0.1 weak\n\
+             q2 Q0 d4 1 0.2 weak\n"
+        );
+
+        let scores = MAP.evaluate_queries(&ranker, &dataset, &policy).unwrap();
+        assert_eq!(scores.len(), 2);
+        assert_eq!(scores[0].query_id, "q1");
+        assert_eq!(scores[1].score, 1.0);
+
+        let mut report = Vec::new();
+        write_evaluation(&mut report, &MAP.to_string(), &scores).unwrap();
+        assert_eq!(
+            String::from_utf8(report).unwrap(),
+            "MAP\tq1\t1.0000\nMAP\tq2\t1.0000\nMAP\tall\t1.0000\n"
+        );
+    }
+}
@@ -0,0 +1,289 @@
+/// Copyright (c) 2021 Marcos Pontes
+// This code is licensed under MIT license (see LICENSE for details)
+use std::collections::BTreeMap;
+
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use crate::{error::LtrError, eval::is_relevant, DataSet};
+
+/// How the queries are spread across the splits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Stratify {
+    /// The queries are shuffled and spread at random.
+    #[default]
+    None,
+    /// Each split receives queries with a similar distribution of sizes (documents per query).
+    QuerySize,
+    /// Each split receives queries with a similar distribution of relevant documents.
+    RelevantCount,
+}
+
+/// A training, validation and test split of a `DataSet`.
+#[derive(Clone, Default)]
+pub struct DataSplit {
+    /// The training `DataSet`.
+    pub train: DataSet,
+    /// The validation `DataSet`.
+    pub validation: DataSet,
+    /// The test `DataSet`.
+    pub test: DataSet,
+}
+
+/// Split a `DataSet` by query: all the documents of a query go to the same split.
+///
+/// The queries are shuffled with a seedable RNG, so the same seed always gives the same splits.
+#[derive(Debug, Clone)]
+pub struct Splitter {
+    /// Seed of the RNG.
+    seed: u64,
+    /// How the queries are spread across the splits.
+    stratify: Stratify,
+}
+
+impl Splitter {
+    /// Creates a new `Splitter` instance.
+    ///
+    /// # Arguments
+    /// * `seed` - The seed of the RNG used to shuffle the queries.
+    pub fn new(seed: u64) -> Splitter {
+        Splitter {
+            seed,
+            stratify: Stratify::None,
+        }
+    }
+
+    /// Get the seed of the RNG.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Set how the queries are spread across the splits.
+    ///
+    /// # Arguments
+    /// * `stratify` - The stratification strategy.
+    pub fn set_stratify(&mut self, stratify: Stratify) {
+        self.stratify = stratify;
+    }
+
+    /// Split the `DataSet` in as many parts as ratios.
+    ///
+    /// # Arguments
+    /// * `dataset` - The `DataSet` to be split.
+    /// * `ratios` - The fraction of the queries of each part. They must sum to 1.
+    ///
+    /// # Returns
+    /// One `DataSet` per ratio. The queries of each part keep their original order.
+    ///
+    /// # Errors
+    /// `LtrError::ConfigurationError` if a ratio is negative or the ratios don't sum to 1.
+    pub fn split(&self, dataset: &DataSet, ratios: &[f32]) -> Result<Vec<DataSet>, LtrError> {
+        self.assign(dataset, ratios)?
+            .iter()
+            .map(|part| dataset.select(part))
+            .collect()
+    }
+
+    /// Assign the queries of the `DataSet` to as many parts as ratios.
+    ///
+    /// # Returns
+    /// The positions of the `RankList`s of each part, in increasing order.
+    pub(crate) fn assign(
+        &self,
+        dataset: &DataSet,
+        ratios: &[f32],
+    ) -> Result<Vec<Vec<usize>>, LtrError> {
+        if ratios.is_empty() || ratios.iter().any(|ratio| !(0.0..=1.0).contains(ratio)) {
+            return Err(LtrError::ConfigurationError(
+                "The split ratios must be between 0 and 1.",
+            ));
+        }
+        if (ratios.iter().sum::<f32>() - 1.0).abs() > 1e-4 {
+            return Err(LtrError::ConfigurationError(
+                "The split ratios must sum to 1.",
+            ));
+        }
+
+        let mut parts: Vec<Vec<usize>> = vec![Vec::new(); ratios.len()];
+        for (position, index) in self.ordered_queries(dataset).into_iter().enumerate() {
+            // The part furthest behind its share receives the next query, so every
+            // stratum (a run of this order) is spread proportionally.
+            let assigned = position + 1;
+            let part = (0..ratios.len())
+                .max_by(|&a, &b| {
+                    let deficit_a = ratios[a] * assigned as f32 - parts[a].len() as f32;
+                    let deficit_b = ratios[b] * assigned as f32 - parts[b].len() as f32;
+                    deficit_a.total_cmp(&deficit_b).then(b.cmp(&a))
+                })
+                .unwrap_or(0);
+            parts[part].push(index);
+        }
+
+        parts.iter_mut().for_each(|part| part.sort_unstable());
+        Ok(parts)
+    }
+
+    /// Split the `DataSet` in a training and a test `DataSet`.
+    ///
+    /// # Arguments
+    /// * `dataset` - The `DataSet` to be split.
+    /// * `test_ratio` - The fraction of the queries in the test `DataSet`.
+    ///
+    /// # Errors
+    /// `LtrError::ConfigurationError` if the ratio is not between 0 and 1.
+    pub fn train_test_split(
+        &self,
+        dataset: &DataSet,
+        test_ratio: f32,
+    ) -> Result<(DataSet, DataSet), LtrError> {
+        let mut parts = self.split(dataset, &[1.0 - test_ratio, test_ratio])?;
+        let test = parts.pop().unwrap_or_default();
+        let train = parts.pop().unwrap_or_default();
+        Ok((train, test))
+    }
+
+    /// Split the `DataSet` in a training, a validation and a test `DataSet`.
+    ///
+    /// # Arguments
+    /// * `dataset` - The `DataSet` to be split.
+    /// * `validation_ratio` - The fraction of the queries in the validation `DataSet`.
+    /// * `test_ratio` - The fraction of the queries in the test `DataSet`.
+    ///
+    /// # Errors
+    /// `LtrError::ConfigurationError` if the ratios are not between 0 and 1 or sum more than 1.
+    pub fn train_validation_test_split(
+        &self,
+        dataset: &DataSet,
+        validation_ratio: f32,
+        test_ratio: f32,
+    ) -> Result<DataSplit, LtrError> {
+        let train_ratio = 1.0 - validation_ratio - test_ratio;
+        let mut parts = self.split(dataset, &[train_ratio, validation_ratio, test_ratio])?;
+        let test = parts.pop().unwrap_or_default();
+        let validation = parts.pop().unwrap_or_default();
+        let train = parts.pop().unwrap_or_default();
+        Ok(DataSplit {
+            train,
+            validation,
+            test,
+        })
+    }
+
+    /// Shuffle the positions of the queries and, when stratifying, group them by stratum.
+    fn ordered_queries(&self, dataset: &DataSet) -> Vec<usize> {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut order: Vec<usize> = (0..dataset.len()).collect();
+        order.shuffle(&mut rng);
+
+        let stratum = |index: usize| -> usize {
+            let ranklist = &dataset[index];
+            match self.stratify {
+                Stratify::None => 0,
+                Stratify::QuerySize => ranklist.len(),
+                Stratify::RelevantCount => ranklist
+                    .data_points()
+                    .iter()
+                    .filter(|dp| is_relevant(dp.get_label()))
+                    .count(),
+            }
+        };
+
+        let mut strata: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for index in order {
+            strata.entry(stratum(index)).or_default().push(index);
+        }
+        strata.into_values().flatten().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{datapoint::DataPoint, ranklist::RankList, rl};
+
+    fn dataset(num_queries: usize) -> DataSet {
+        (0..num_queries)
+            .map(|q| {
+                let query_id = format!("q{}", q);
+                let size = 1 + q % 3;
+                RankList::new(
+                    (0..size)
+                        .map(|d| {
+                            let label = if d == 0 && q % 2 == 0 { 1 } else { 0 };
+                            DataPoint::new(label, query_id.as_str(), vec![d as f32], None)
+                        })
+                        .collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_split() {
+        let dataset = dataset(20);
+        let splitter = Splitter::new(42);
+        let split = splitter
+            .train_validation_test_split(&dataset, 0.2, 0.1)
+            .unwrap();
+        assert_eq!(split.train.len(), 14);
+        assert_eq!(split.validation.len(), 4);
+        assert_eq!(split.test.len(), 2);
+
+        // No query is shared between the splits.
+        let mut query_ids: Vec<_> = [&split.train, &split.validation, &split.test]
+            .iter()
+            .flat_map(|part| part.query_ids())
+            .collect();
+        query_ids.sort();
+        query_ids.dedup();
+        assert_eq!(query_ids.len(), 20);
+
+        // The same seed gives the same splits.
+        let again = Splitter::new(42)
+            .train_validation_test_split(&dataset, 0.2, 0.1)
+            .unwrap();
+        assert_eq!(again.test.query_ids(), split.test.query_ids());
+
+        assert!(splitter.split(&dataset, &[0.5, 0.6]).is_err());
+        assert!(splitter.split(&dataset, &[-0.5, 1.5]).is_err());
+        let (train, test) = splitter.train_test_split(&dataset, 0.0).unwrap();
+        assert_eq!((train.len(), test.len()), (20, 0));
+    }
+
+    #[test]
+    fn test_stratified_split() {
+        let dataset = dataset(12);
+        let mut splitter = Splitter::new(7);
+
+        splitter.set_stratify(Stratify::QuerySize);
+        let (train, test) = splitter.train_test_split(&dataset, 0.5).unwrap();
+        for size in 1..=3 {
+            let count = |part: &DataSet| part.iter().filter(|rl| rl.len() == size).count();
+            assert_eq!(count(&train), 2);
+            assert_eq!(count(&test), 2);
+        }
+
+        splitter.set_stratify(Stratify::RelevantCount);
+        let (train, test) = splitter.train_test_split(&dataset, 0.5).unwrap();
+        let relevant = |part: &DataSet| {
+            part.iter()
+                .filter(|rl| rl.get(0).unwrap().get_label() > 0.0)
+                .count()
+        };
+        assert_eq!(relevant(&train), 3);
+        assert_eq!(relevant(&test), 3);
+    }
+
+    #[test]
+    fn test_partition_queries() {
+        let dataset = DataSet::from(vec![
+            rl!((1, "q1", vec![0.1])),
+            rl!((0, "q2", vec![0.2, 0.3])),
+            rl!((1, "q3", vec![0.4])),
+        ]);
+        let (validation, train) = dataset.partition_queries(&["q3", "q1"]).unwrap();
+        assert_eq!(validation.query_ids(), vec!["q1", "q3"]);
+        assert_eq!(train.query_ids(), vec!["q2"]);
+        assert_eq!(validation.num_features(), 2);
+        assert!(dataset.partition_queries(&["q4"]).is_err());
+    }
+}
@@ -0,0 +1,742 @@
+/// Copyright (c) 2021 Marcos Pontes
+// This code is licensed under MIT license (see LICENSE for details)
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    datapoint::DataPoint, dataset::FeatureNames, error::LtrError, learner::FileSerializable,
+    normalize::Normalizer, ranker::Ranker, ranklist::RankList, DataSet, Feature,
+};
+
+/// How missing (NaN or infinite) feature values are replaced.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ImputeStrategy {
+    /// Replace with a constant value.
+    Constant(Feature),
+    /// Replace with the mean of the finite values of the feature.
+    Mean,
+    /// Replace with the median of the finite values of the feature.
+    Median,
+}
+
+/// A step of a feature transformation `Pipeline`.
+///
+/// Features are referred to by their (1-based) index in the input of the step.
+/// Features missing in a `DataPoint` are zero, as in the SVM-Light format.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Transform {
+    /// Replace each value `x` with `sign(x) * ln(1 + |x|)`.
+    /// Only the given features are transformed, or all of them if `None`.
+    Log1p {
+        /// The transformed features.
+        features: Option<Vec<usize>>,
+    },
+    /// Clip each value to `[min, max]`.
+    /// Only the given features are clipped, or all of them if `None`.
+    Clip {
+        /// Lowest value.
+        min: Feature,
+        /// Highest value.
+        max: Feature,
+        /// The clipped features.
+        features: Option<Vec<usize>>,
+    },
+    /// Replace the missing (NaN or infinite) values. The mean and median are fitted.
+    Impute {
+        /// How the missing values are replaced.
+        strategy: ImputeStrategy,
+        /// Fitted replacement of each feature, the feature `i` being at the position `i - 1`.
+        values: Option<Vec<Feature>>,
+    },
+    /// Keep only the given features, in the given order: the first one becomes the feature 1.
+    Select {
+        /// The kept features.
+        features: Vec<usize>,
+    },
+    /// Append the product of each pair of features as a new feature.
+    ///
+    /// The products are appended after the fitted number of input features, so that
+    /// they are in the same columns for every document, whatever its own length.
+    Interaction {
+        /// The multiplied features.
+        pairs: Vec<(usize, usize)>,
+        /// Fitted number of input features.
+        #[serde(default)]
+        num_features: Option<usize>,
+    },
+    /// Normalize the features. Per-query normalizers can only be applied to whole `DataSet`s.
+    Normalize(Normalizer),
+}
+
+impl Transform {
+    /// Build a `Transform::Impute` step, to be fitted.
+    pub fn impute(strategy: ImputeStrategy) -> Transform {
+        Transform::Impute {
+            strategy,
+            values: None,
+        }
+    }
+
+    /// Build a `Transform::Interaction` step, to be fitted.
+    pub fn interaction(pairs: Vec<(usize, usize)>) -> Transform {
+        Transform::Interaction {
+            pairs,
+            num_features: None,
+        }
+    }
+
+    /// Fit the step on a `DataSet`. Steps without statistics are left unchanged.
+    ///
+    /// # Arguments
+    /// * `dataset` - The input `DataSet` of the step.
+    pub fn fit(&mut self, dataset: &DataSet) {
+        match self {
+            Transform::Impute { strategy, values } => {
+                let replacements = (1..=dataset.num_features())
+                    .map(|index| {
+                        let mut finite: Vec<Feature> = dataset
+                            .iter()
+                            .flat_map(|ranklist| {
+                                ranklist
+                                    .data_points()
+                                    .iter()
+                                    .map(|dp| dp.get_feature(index).copied().unwrap_or(0.0))
+                                    .collect::<Vec<_>>()
+                            })
+                            .filter(|value| value.is_finite())
+                            .collect();
+                        match strategy {
+                            ImputeStrategy::Constant(value) => *value,
+                            _ if finite.is_empty() => 0.0,
+                            ImputeStrategy::Mean => {
+                                finite.iter().sum::<Feature>() / finite.len() as Feature
+                            }
+                            ImputeStrategy::Median => {
+                                finite.sort_by(|a, b| a.total_cmp(b));
+                                let middle = finite.len() / 2;
+                                match finite.len() % 2 {
+                                    0 => (finite[middle - 1] + finite[middle]) / 2.0,
+                                    _ => finite[middle],
+                                }
+                            }
+                        }
+                    })
+                    .collect();
+                *values = Some(replacements);
+            }
+            Transform::Interaction { num_features, .. } => {
+                *num_features = Some(dataset.num_features());
+            }
+            Transform::Normalize(normalizer) => normalizer.fit(dataset),
+            _ => {}
+        }
+    }
+
+    /// Check whether the step can be applied to single documents.
+    pub fn is_fitted(&self) -> bool {
+        match self {
+            Transform::Impute { strategy, values } => {
+                values.is_some() || matches!(strategy, ImputeStrategy::Constant(_))
+            }
+            Transform::Interaction { num_features, .. } => num_features.is_some(),
+            Transform::Normalize(normalizer) => normalizer.is_fitted(),
+            _ => true,
+        }
+    }
+
+    /// Apply the step to the features of a document.
+    ///
+    /// # Arguments
+    /// * `features` - The features, the feature `i` being at the position `i - 1`.
+    ///
+    /// # Errors
+    /// `LtrError::ConfigurationError` if the step must be fitted first or is a per-query
+    /// normalization.
+    pub fn apply(&self, mut features: Vec<Feature>) -> Result<Vec<Feature>, LtrError> {
+        let value = |features: &[Feature], index: usize| -> Feature {
+            features.get(index.wrapping_sub(1)).copied().unwrap_or(0.0)
+        };
+        let selected = |selection: &Option<Vec<usize>>, index: usize| -> bool {
+            selection
+                .as_ref()
+                .is_none_or(|selection| selection.contains(&index))
+        };
+
+        match self {
+            Transform::Log1p {
+                features: selection,
+            } => {
+                for (i, x) in features.iter_mut().enumerate() {
+                    if selected(selection, i + 1) {
+                        *x = x.signum() * x.abs().ln_1p();
+                    }
+                }
+            }
+            Transform::Clip {
+                min,
+                max,
+                features: selection,
+            } => {
+                for (i, x) in features.iter_mut().enumerate() {
+                    if selected(selection, i + 1) {
+                        *x = x.clamp(*min, *max);
+                    }
+                }
+            }
+            Transform::Impute { strategy, values } => {
+                for (i, x) in features.iter_mut().enumerate() {
+                    if !x.is_finite() {
+                        *x = match (strategy, values) {
+                            (ImputeStrategy::Constant(value), _) => *value,
+                            (_, Some(values)) => values.get(i).copied().unwrap_or(0.0),
+                            (_, None) => {
+                                return Err(LtrError::ConfigurationError(
+                                    "The imputation must be fitted first.",
+                                ))
+                            }
+                        };
+                    }
+                }
+            }
+            Transform::Select {
+                features: selection,
+            } => {
+                features = selection.iter().map(|&i| value(&features, i)).collect();
+            }
+            Transform::Interaction {
+                pairs,
+                num_features,
+            } => {
+                let num_features = num_features.ok_or(LtrError::ConfigurationError(
+                    "The interaction must be fitted first.",
+                ))?;
+                let products: Vec<Feature> = pairs
+                    .iter()
+                    .map(|&(a, b)| value(&features, a) * value(&features, b))
+                    .collect();
+                features.resize(num_features, 0.0);
+                features.extend(products);
+            }
+            Transform::Normalize(normalizer) => {
+                // The features missing at the end are zero, as when fitting the statistics.
+                if let Some(stats) = normalizer.stats() {
+                    features.resize(features.len().max(stats.len()), 0.0);
+                }
+                normalizer.transform_features(&mut features)?;
+            }
+        }
+        Ok(features)
+    }
+
+    /// Apply the step to every document of a `DataSet`.
+    ///
+    /// # Arguments
+    /// * `dataset` - The input `DataSet` of the step.
+    ///
+    /// # Returns
+    /// A new `DataSet`, with the feature names updated.
+    pub fn apply_dataset(&self, dataset: &DataSet) -> Result<DataSet, LtrError> {
+        let mut output = match self {
+            Transform::Normalize(normalizer) => {
+                let output = dataset.clone();
+                normalizer.transform(&output)?;
+                output
+            }
+            _ => {
+                let mut ranklists = Vec::with_capacity(dataset.len());
+                for ranklist in dataset.iter() {
+                    let mut data_points = Vec::with_capacity(ranklist.len());
+                    for dp in ranklist.data_points().iter() {
+                        data_points.push(self.apply_data_point(dp)?);
+                    }
+                    ranklists.push(RankList::new(data_points));
+                }
+                DataSet::from(ranklists)
+            }
+        };
+        if let Some(source) = dataset.source() {
+            output.set_source(source);
+        }
+        if let Some(names) = dataset.feature_names() {
+            output.set_feature_names(self.feature_names(names));
+        }
+        Ok(output)
+    }
+
+    /// Get the name of the output features of the step.
+    ///
+    /// # Arguments
+    /// * `names` - The name of the input features.
+    ///
+    /// # Returns
+    /// The names of the input features, updated by the step. Interactions that are
+    /// not fitted add no names.
+    pub fn feature_names(&self, names: &FeatureNames) -> FeatureNames {
+        match self {
+            Transform::Select { features } => {
+                let mut output = FeatureNames::new();
+                for (i, &feature) in features.iter().enumerate() {
+                    if let Some(name) = names.name(feature) {
+                        output.insert(i + 1, name);
+                    }
+                }
+                output
+            }
+            Transform::Interaction {
+                pairs,
+                num_features: Some(num_features),
+            } => {
+                let mut output = names.clone();
+                for (i, &(a, b)) in pairs.iter().enumerate() {
+                    let name = format!("{}*{}", names.label(a), names.label(b));
+                    output.insert(num_features + i + 1, &name);
+                }
+                output
+            }
+            _ => names.clone(),
+        }
+    }
+
+    /// Apply the step to a document.
+    fn apply_data_point(&self, data_point: &DataPoint) -> Result<DataPoint, LtrError> {
+        let mut output = data_point.clone();
+        output.set_features(self.apply(data_point.features().to_dense())?)?;
+        if data_point.features().is_sparse() {
+            output.set_feature_storage(crate::datapoint::FeatureStorage::Sparse);
+        }
+        Ok(output)
+    }
+}
+
+/// A sequence of feature transformations, fitted on a training `DataSet` and applied
+/// to the validation and test `DataSet`s, and to single documents at inference time.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Pipeline {
+    /// The steps, applied in order.
+    steps: Vec<Transform>,
+}
+
+impl Pipeline {
+    /// Creates an empty `Pipeline`.
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    /// Add a step at the end of the pipeline.
+    ///
+    /// # Arguments
+    /// * `step` - The transformation to be added.
+    pub fn add(&mut self, step: Transform) {
+        self.steps.push(step);
+    }
+
+    /// Get the steps of the pipeline.
+    pub fn steps(&self) -> &[Transform] {
+        &self.steps
+    }
+
+    /// Check whether every step can be applied to single documents.
+    pub fn is_fitted(&self) -> bool {
+        self.steps.iter().all(|step| step.is_fitted())
+    }
+
+    /// Fit the steps on a training `DataSet`. Each step is fitted on the output
+    /// of the previous ones.
+    ///
+    /// # Arguments
+    /// * `dataset` - The training `DataSet`.
+    ///
+    /// # Errors
+    /// `LtrError` if a step could not be applied.
+    pub fn fit(&mut self, dataset: &DataSet) -> Result<(), LtrError> {
+        self.fit_transform(dataset).map(|_| ())
+    }
+
+    /// Fit the steps on a training `DataSet` and transform it.
+    ///
+    /// # Arguments
+    /// * `dataset` - The training `DataSet`.
+    ///
+    /// # Errors
+    /// `LtrError` if a step could not be applied.
+    pub fn fit_transform(&mut self, dataset: &DataSet) -> Result<DataSet, LtrError> {
+        let mut output = dataset.clone();
+        for step in self.steps.iter_mut() {
+            step.fit(&output);
+            output = step.apply_dataset(&output)?;
+        }
+        Ok(output)
+    }
+
+    /// Transform a `DataSet` with the fitted steps.
+    ///
+    /// # Arguments
+    /// * `dataset` - The `DataSet` to be transformed.
+    ///
+    /// # Errors
+    /// `LtrError::ConfigurationError` if a step must be fitted first.
+    pub fn transform(&self, dataset: &DataSet) -> Result<DataSet, LtrError> {
+        let mut output = dataset.clone();
+        for step in self.steps.iter() {
+            output = step.apply_dataset(&output)?;
+        }
+        Ok(output)
+    }
+
+    /// Transform the features of a single document with the fitted steps.
+    ///
+    /// # Arguments
+    /// * `features` - The features, the feature `i` being at the position `i - 1`.
+    ///
+    /// # Errors
+    /// `LtrError::ConfigurationError` if a step must be fitted first or is a per-query
+    /// normalization.
+    pub fn transform_features(&self, features: &[Feature]) -> Result<Vec<Feature>, LtrError> {
+        let mut output = features.to_vec();
+        for step in self.steps.iter() {
+            output = step.apply(output)?;
+        }
+        Ok(output)
+    }
+
+    /// Transform a single document with the fitted steps.
+    ///
+    /// # Errors
+    /// `LtrError::ConfigurationError` if a step must be fitted first or is a per-query
+    /// normalization.
+    pub fn transform_data_point(&self, data_point: &DataPoint) -> Result<DataPoint, LtrError> {
+        let mut output = data_point.clone();
+        for step in self.steps.iter() {
+            output = step.apply_data_point(&output)?;
+        }
+        Ok(output)
+    }
+}
+
+impl FileSerializable for Pipeline {
+    fn to_json(&self) -> Result<String, LtrError> {
+        serde_json::to_string_pretty(self).map_err(|e| LtrError::SerializationError(e.to_string()))
+    }
+
+    fn load_json(&mut self, json: &str) -> Result<(), LtrError> {
+        *self =
+            serde_json::from_str(json).map_err(|e| LtrError::SerializationError(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// A `Ranker` scoring raw documents: they are transformed by a fitted `Pipeline` before
+/// being scored by a `Ranker` trained on the transformed features.
+///
+/// The pipeline and the model are saved together in a single file.
+#[derive(Debug, Clone)]
+pub struct PipelineRanker<R> {
+    /// The fitted feature transformations.
+    pipeline: Pipeline,
+    /// The model trained on the transformed features.
+    ranker: R,
+}
+
+impl<R: Ranker> PipelineRanker<R> {
+    /// Creates a new `PipelineRanker` instance.
+    ///
+    /// # Arguments
+    /// * `pipeline` - The fitted pipeline.
+    /// * `ranker` - The model trained on the output of the pipeline.
+    ///
+    /// # Errors
+    /// `LtrError::ConfigurationError` if a step can't be applied to single documents.
+    pub fn new(pipeline: Pipeline, ranker: R) -> Result<PipelineRanker<R>, LtrError> {
+        if !pipeline.is_fitted() {
+            return Err(LtrError::ConfigurationError(
+                "The pipeline must be fitted and have no per-query normalization.",
+            ));
+        }
+        Ok(PipelineRanker { pipeline, ranker })
+    }
+
+    /// Get the pipeline.
+    pub fn pipeline(&self) -> &Pipeline {
+        &self.pipeline
+    }
+
+    /// Get the wrapped model.
+    pub fn ranker(&self) -> &R {
+        &self.ranker
+    }
+
+    /// Consume the `PipelineRanker`, returning the pipeline and the model.
+    pub fn into_parts(self) -> (Pipeline, R) {
+        (self.pipeline, self.ranker)
+    }
+}
+
+impl<R: Ranker> Ranker for PipelineRanker<R> {
+    fn predict(&self, datapoint: &DataPoint) -> f32 {
+        match self.pipeline.transform_data_point(datapoint) {
+            Ok(transformed) => self.ranker.predict(&transformed),
+            Err(e) => {
+                tracing::error!("Error transforming the features: {}", e);
+                f32::NAN
+            }
+        }
+    }
+
+    fn predict_features(&self, features: &[f32]) -> f32 {
+        match self.pipeline.transform_features(features) {
+            Ok(transformed) => self.ranker.predict_features(&transformed),
+            Err(e) => {
+                tracing::error!("Error transforming the features: {}", e);
+                f32::NAN
+            }
+        }
+    }
+}
+
+impl<R: FileSerializable> FileSerializable for PipelineRanker<R> {
+    fn to_json(&self) -> Result<String, LtrError> {
+        let model: serde_json::Value = serde_json::from_str(&self.ranker.to_json()?)
+            .map_err(|e| LtrError::SerializationError(e.to_string()))?;
+        serde_json::to_string_pretty(&serde_json::json!({
+            "pipeline": self.pipeline,
+            "model": model,
+        }))
+        .map_err(|e| LtrError::SerializationError(e.to_string()))
+    }
+
+    fn load_json(&mut self, json: &str) -> Result<(), LtrError> {
+        let mut value: serde_json::Value =
+            serde_json::from_str(json).map_err(|e| LtrError::SerializationError(e.to_string()))?;
+        let missing =
+            |field: &str| LtrError::SerializationError(format!("missing field `{}`", field));
+        let pipeline = value
+            .get_mut("pipeline")
+            .ok_or_else(|| missing("pipeline"))?
+            .take();
+        let pipeline: Pipeline = serde_json::from_value(pipeline)
+            .map_err(|e| LtrError::SerializationError(e.to_string()))?;
+        if !pipeline.is_fitted() {
+            return Err(LtrError::SerializationError(
+                "the pipeline is not fitted".to_string(),
+            ));
+        }
+        let model = value.get("model").ok_or_else(|| missing("model"))?;
+        self.ranker.load_json(&model.to_string())?;
+        self.pipeline = pipeline;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ensemble::{adarank::AdaRank, weak::WeakRanker},
+        eval::map::MAP,
+        learner::Learner,
+        normalize::{NormalizationMethod, NormalizationScope},
+        rl,
+    };
+
+    fn dataset() -> DataSet {
+        let mut dataset = DataSet::from(vec![
+            rl!(
+                (1, "q1", vec![1.0, f32::NAN, 100.0]),
+                (0, "q1", vec![3.0, 2.0, -5.0])
+            ),
+            rl!(
+                (1, "q2", vec![2.0, 4.0, 0.0]),
+                (0, "q2", vec![6.0, 0.0, 7.0])
+            ),
+        ]);
+        dataset.set_feature_names(FeatureNames::parse("1 TF\n2 IDF\n3 BM25").unwrap());
+        dataset
+    }
+
+    #[test]
+    fn test_transforms() {
+        let log1p = Transform::Log1p {
+            features: Some(vec![2]),
+        };
+        assert_eq!(
+            log1p.apply(vec![3.0, -(1.0f32.exp() - 1.0)]).unwrap(),
+            vec![3.0, -1.0]
+        );
+
+        let clip = Transform::Clip {
+            min: 0.0,
+            max: 10.0,
+            features: None,
+        };
+        assert_eq!(
+            clip.apply(vec![-1.0, 5.0, 20.0]).unwrap(),
+            vec![0.0, 5.0, 10.0]
+        );
+
+        let select = Transform::Select {
+            features: vec![3, 1, 4],
+        };
+        assert_eq!(
+            select.apply(vec![1.0, 2.0, 3.0]).unwrap(),
+            vec![3.0, 1.0, 0.0]
+        );
+
+        let mut interaction = Transform::interaction(vec![(1, 2)]);
+        assert!(interaction.apply(vec![2.0, 3.0]).is_err());
+        interaction.fit(&dataset());
+        assert_eq!(
+            interaction.apply(vec![2.0, 3.0]).unwrap(),
+            vec![2.0, 3.0, 0.0, 6.0]
+        );
+
+        let mut median = Transform::impute(ImputeStrategy::Median);
+        assert!(median.apply(vec![f32::NAN]).is_err());
+        median.fit(&dataset());
+        assert_eq!(
+            median.apply(vec![f32::NAN, f32::NAN]).unwrap(),
+            vec![2.5, 2.0]
+        );
+        let constant = Transform::impute(ImputeStrategy::Constant(-1.0));
+        assert_eq!(constant.apply(vec![f32::INFINITY]).unwrap(), vec![-1.0]);
+    }
+
+    #[test]
+    fn test_pipeline() {
+        let mut pipeline = Pipeline::new();
+        pipeline.add(Transform::impute(ImputeStrategy::Mean));
+        pipeline.add(Transform::Clip {
+            min: 0.0,
+            max: 50.0,
+            features: Some(vec![3]),
+        });
+        pipeline.add(Transform::Select {
+            features: vec![3, 1],
+        });
+        pipeline.add(Transform::interaction(vec![(1, 2)]));
+        pipeline.add(Transform::Normalize(Normalizer::new(
+            NormalizationMethod::Linear,
+            NormalizationScope::Global,
+        )));
+        assert!(!pipeline.is_fitted());
+
+        let train = pipeline.fit_transform(&dataset()).unwrap();
+        assert!(pipeline.is_fitted());
+        assert_eq!(train.num_features(), 3);
+        let names = train.feature_names().unwrap();
+        assert_eq!(names.name(1), Some("BM25"));
+        assert_eq!(names.name(2), Some("TF"));
+        assert_eq!(names.name(3), Some("BM25*TF"));
+        assert_eq!(
+            train[0].get(0).unwrap().features().to_dense(),
+            vec![1.0, 0.0, 1.0]
+        );
+
+        // The fitted pipeline gives the same result on a dataset and on single documents.
+        let test = pipeline.transform(&dataset()).unwrap();
+        let raw = [3.0, 2.0, -5.0];
+        assert_eq!(
+            pipeline.transform_features(&raw).unwrap(),
+            test[0].get(1).unwrap().features().to_dense()
+        );
+
+        let mut per_query = pipeline.clone();
+        per_query.add(Transform::Normalize(Normalizer::new(
+            NormalizationMethod::Sum,
+            NormalizationScope::PerQuery,
+        )));
+        assert!(per_query.transform(&dataset()).is_ok());
+        assert!(per_query.transform_features(&raw).is_err());
+        assert!(PipelineRanker::new(per_query, WeakRanker::new(1)).is_err());
+    }
+
+    #[test]
+    fn test_interaction_names() {
+        // The last features have no names: the interactions go after them.
+        let mut dataset = dataset();
+        dataset.set_feature_names(FeatureNames::parse("1 TF").unwrap());
+        let mut interaction = Transform::interaction(vec![(1, 3)]);
+        interaction.fit(&dataset);
+        let output = interaction.apply_dataset(&dataset).unwrap();
+        assert_eq!(output.num_features(), 4);
+        let names = output.feature_names().unwrap();
+        assert_eq!(names.name(3), None);
+        assert_eq!(names.name(4), Some("TF*3"));
+
+        let unnamed = interaction.feature_names(&FeatureNames::new());
+        assert_eq!(unnamed.iter().collect::<Vec<_>>(), vec![(4, "1*3")]);
+    }
+
+    #[test]
+    fn test_interaction_columns() {
+        // Documents leaving out their last features get the products in the training columns.
+        let mut pipeline = Pipeline::new();
+        pipeline.add(Transform::interaction(vec![(1, 2)]));
+        let train = pipeline.fit_transform(&dataset()).unwrap();
+        assert_eq!(train.num_features(), 4);
+        assert_eq!(
+            train[1].get(0).unwrap().features().to_dense(),
+            vec![2.0, 4.0, 0.0, 8.0]
+        );
+
+        let short = vec![2.0, 4.0];
+        let mut sparse = DataPoint::new(0, "q", vec![], None);
+        sparse
+            .set_sparse_features(vec![(1, 2.0), (2, 4.0)])
+            .unwrap();
+        let test = DataSet::from(vec![RankList::new(vec![sparse.clone()])]);
+        let expected = vec![2.0, 4.0, 0.0, 8.0];
+        assert_eq!(pipeline.transform_features(&short).unwrap(), expected);
+        assert_eq!(
+            pipeline
+                .transform_data_point(&sparse)
+                .unwrap()
+                .get_feature(4)
+                .copied()
+                .unwrap(),
+            8.0
+        );
+        let transformed = pipeline.transform(&test).unwrap();
+        assert_eq!(transformed.num_features(), 4);
+        assert_eq!(
+            transformed[0].get(0).unwrap().features().to_dense(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_pipeline_ranker() {
+        let mut pipeline = Pipeline::new();
+        pipeline.add(Transform::impute(ImputeStrategy::Constant(0.0)));
+        pipeline.add(Transform::Log1p { features: None });
+        let train = pipeline.fit_transform(&dataset()).unwrap();
+
+        let mut adarank = AdaRank::new(train, Box::new(MAP), 3, 100, 1.0, None, None);
+        adarank.fit().unwrap();
+        let raw = vec![2.0, f32::NAN, 3.0];
+        let expected = adarank.predict_features(&pipeline.transform_features(&raw).unwrap());
+
+        let ranker = PipelineRanker::new(pipeline.clone(), adarank).unwrap();
+        assert_eq!(ranker.predict_features(&raw), expected);
+        assert_eq!(
+            ranker.predict(&DataPoint::new(0, "q", raw.clone(), None)),
+            expected
+        );
+
+        let path = std::env::temp_dir().join("adarank_test_pipeline_ranker.json");
+        let path = path.to_str().unwrap();
+        ranker.save_to_file(path).unwrap();
+        let untrained = AdaRank::new(DataSet::new(), Box::new(MAP), 1, 100, 1.0, None, None);
+        let mut loaded = PipelineRanker::new(Pipeline::new(), untrained).unwrap();
+        loaded.load_from_file(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(loaded.pipeline(), &pipeline);
+        assert_eq!(loaded.predict_features(&raw), expected);
+
+        // Valid JSON that isn't a saved pipeline ranker.
+        for json in ["[]", "1", "{\"pipeline\": []}"] {
+            assert!(matches!(
+                loaded.load_json(json),
+                Err(LtrError::SerializationError(_))
+            ));
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use std::{
-    io::{Error, ErrorKind, Write},
-    iter::repeat,
+    io::{Error, Write},
+    iter::repeat_n,
     str::FromStr,
 };
 
@@ -90,7 +90,7 @@ impl Table {
         size: usize,
     ) -> Result<(), Error> {
         let text_len = Table::display_width(text);
-        let mut nfill = if text_len < size { size - text_len } else { 0 };
+        let mut nfill = size.saturating_sub(text_len);
 
         let n = match align {
             Alignment::Left => 0,
@@ -99,14 +99,14 @@ impl Table {
         };
 
         if n > 0 {
-            out.write_all(repeat(fill).take(n).collect::<String>().as_bytes())?;
+            out.write_all(repeat_n(fill, n).collect::<String>().as_bytes())?;
             nfill -= n;
         }
 
         out.write_all(text.as_bytes())?;
 
         if nfill > 0 {
-            out.write_all(repeat(fill).take(nfill).collect::<String>().as_bytes())?;
+            out.write_all(repeat_n(fill, nfill).collect::<String>().as_bytes())?;
         }
 
         Ok(())
@@ -130,9 +130,7 @@ impl Table {
             col_width
         };
 
-        let mut iter = value.iter().zip(col_width.iter()).peekable();
-
-        while let Some((v, w)) = iter.next() {
+        for (v, w) in value.iter().zip(col_width.iter()) {
             Table::align(out, align, &v.to_string(), ' ', *w + padding.0 + padding.1)?;
         }
 
@@ -202,10 +200,10 @@ impl Write for StringWriter {
         let string = match std::str::from_utf8(data) {
             Ok(s) => s,
             Err(e) => {
-                return Err(std::io::Error::new(
-                    ErrorKind::Other,
-                    format!("Cannot decode utf8 string : {}", e),
-                ))
+                return Err(std::io::Error::other(format!(
+                    "Cannot decode utf8 string : {}",
+                    e
+                )))
             }
         };
         self.string.push_str(string);
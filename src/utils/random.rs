@@ -1,6 +1,6 @@
 use rand;
 
-///! Utility function to generating random data.
+/// Utility function to generating random data.
 pub fn randomize<D, T>(distribution: D, times: usize) -> Vec<T>
 where
     D: rand::distributions::Distribution<T>,
@@ -0,0 +1,425 @@
+/// Copyright (c) 2021 Marcos Pontes
+// This code is licensed under MIT license (see LICENSE for details)
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{datapoint::QueryId, error::LtrError, eval::is_relevant, ranklist::RankList, DataSet};
+
+/// How serious a validation `Issue` is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The data is suspicious, but models can be trained on it.
+    Warning,
+    /// The data is invalid: training or evaluation results would be wrong.
+    Error,
+}
+
+/// A problem found in a `DataSet`. Queries are identified by their query id, or
+/// by their position for empty rank lists, and documents by their position in the query.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Issue {
+    /// A `RankList` holds documents of several queries.
+    MixedQueryIds {
+        /// The position of the `RankList` in the `DataSet`.
+        position: usize,
+        /// The query ids found in the `RankList`.
+        query_ids: Vec<QueryId>,
+    },
+    /// A dense document doesn't have as many features as the `DataSet`.
+    /// Its missing trailing features are zero, as the SVM-Light format allows,
+    /// but the row may also have been truncated.
+    InconsistentDimensions {
+        /// The query.
+        query: String,
+        /// The position of the document.
+        document: usize,
+        /// The number of features of the `DataSet`.
+        expected: usize,
+        /// The number of features of the document.
+        found: usize,
+    },
+    /// A feature is NaN or infinite.
+    NonFiniteFeature {
+        /// The query.
+        query: String,
+        /// The position of the document.
+        document: usize,
+        /// The (1-based) index of the feature.
+        feature: usize,
+    },
+    /// A relevance label is NaN or infinite.
+    NonFiniteLabel {
+        /// The query.
+        query: String,
+        /// The position of the document.
+        document: usize,
+    },
+    /// A `RankList` has no documents.
+    EmptyRankList {
+        /// The position of the `RankList` in the `DataSet`.
+        position: usize,
+    },
+    /// Two documents of a query have the same document id, or the same label and features.
+    DuplicateDocument {
+        /// The query.
+        query: String,
+        /// The position of the first document.
+        first: usize,
+        /// The position of the duplicate.
+        duplicate: usize,
+    },
+    /// A query has no relevant document, so evaluators always score it 0.
+    NoRelevantDocuments {
+        /// The query.
+        query: String,
+    },
+}
+
+impl Issue {
+    /// Get how serious the issue is.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Issue::MixedQueryIds { .. }
+            | Issue::NonFiniteFeature { .. }
+            | Issue::NonFiniteLabel { .. } => Severity::Error,
+            Issue::InconsistentDimensions { .. }
+            | Issue::EmptyRankList { .. }
+            | Issue::DuplicateDocument { .. }
+            | Issue::NoRelevantDocuments { .. } => Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Issue::MixedQueryIds {
+                position,
+                query_ids,
+            } => {
+                let ids: Vec<String> = query_ids.iter().map(|id| id.to_string()).collect();
+                write!(
+                    f,
+                    "RankList {} mixes the queries {}",
+                    position,
+                    ids.join(", ")
+                )
+            }
+            Issue::InconsistentDimensions {
+                query,
+                document,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Query {}, document {}: {} features instead of {}",
+                query, document, found, expected
+            ),
+            Issue::NonFiniteFeature {
+                query,
+                document,
+                feature,
+            } => write!(
+                f,
+                "Query {}, document {}: feature {} is not finite",
+                query, document, feature
+            ),
+            Issue::NonFiniteLabel { query, document } => {
+                write!(
+                    f,
+                    "Query {}, document {}: label is not finite",
+                    query, document
+                )
+            }
+            Issue::EmptyRankList { position } => write!(f, "RankList {} is empty", position),
+            Issue::DuplicateDocument {
+                query,
+                first,
+                duplicate,
+            } => write!(
+                f,
+                "Query {}: document {} duplicates document {}",
+                query, duplicate, first
+            ),
+            Issue::NoRelevantDocuments { query } => {
+                write!(f, "Query {} has no relevant documents", query)
+            }
+        }
+    }
+}
+
+/// The issues found in a `DataSet`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationReport {
+    /// The issues, in the order of the `RankList`s.
+    pub issues: Vec<Issue>,
+}
+
+impl ValidationReport {
+    /// Check whether no error was found. Warnings are allowed.
+    pub fn is_valid(&self) -> bool {
+        self.errors().next().is_none()
+    }
+
+    /// Get the issues with the `Error` severity.
+    pub fn errors(&self) -> impl Iterator<Item = &Issue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity() == Severity::Error)
+    }
+
+    /// Get the issues with the `Warning` severity.
+    pub fn warnings(&self) -> impl Iterator<Item = &Issue> {
+        self.issues
+            .iter()
+            .filter(|issue| issue.severity() == Severity::Warning)
+    }
+
+    /// Turn the report into an error if an error was found.
+    ///
+    /// # Errors
+    /// `LtrError::InvalidDataSet` with the first error found.
+    pub fn check(&self) -> Result<(), LtrError> {
+        match self.errors().next() {
+            Some(issue) => Err(LtrError::InvalidDataSet(issue.to_string())),
+            None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} errors, {} warnings",
+            self.errors().count(),
+            self.warnings().count()
+        )?;
+        for issue in self.issues.iter() {
+            let severity = match issue.severity() {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            };
+            write!(f, "\n{}: {}", severity, issue)?;
+        }
+        Ok(())
+    }
+}
+
+/// Check a `DataSet` before training: mixed query ids inside a `RankList`, inconsistent
+/// feature dimensions, NaN and infinite values, empty rank lists, duplicate documents and
+/// queries without relevant documents.
+///
+/// # Arguments
+/// * `dataset` - The `DataSet` to be checked.
+pub fn validate(dataset: &DataSet) -> ValidationReport {
+    let num_features = dataset.num_features();
+    let mut issues = Vec::new();
+    for (position, ranklist) in dataset.iter().enumerate() {
+        validate_ranklist(position, ranklist, num_features, &mut issues);
+    }
+    ValidationReport { issues }
+}
+
+fn validate_ranklist(
+    position: usize,
+    ranklist: &RankList,
+    num_features: usize,
+    issues: &mut Vec<Issue>,
+) {
+    let data_points = ranklist.data_points();
+    let Some(first) = data_points.first() else {
+        issues.push(Issue::EmptyRankList { position });
+        return;
+    };
+    let query = first.get_query_id().to_string();
+
+    let mut query_ids = vec![first.get_query_id().clone()];
+    for dp in data_points.iter() {
+        if !query_ids.contains(dp.get_query_id()) {
+            query_ids.push(dp.get_query_id().clone());
+        }
+    }
+    if query_ids.len() > 1 {
+        issues.push(Issue::MixedQueryIds {
+            position,
+            query_ids,
+        });
+    }
+
+    let mut doc_ids: HashMap<&str, usize> = HashMap::new();
+    let mut contents: HashMap<(u32, Vec<(usize, u32)>), usize> = HashMap::new();
+    for (document, dp) in data_points.iter().enumerate() {
+        let features = dp.features();
+        if !features.is_sparse() && features.len() != num_features {
+            issues.push(Issue::InconsistentDimensions {
+                query: query.clone(),
+                document,
+                expected: num_features,
+                found: features.len(),
+            });
+        }
+        for (feature, value) in features.iter() {
+            if !value.is_finite() {
+                issues.push(Issue::NonFiniteFeature {
+                    query: query.clone(),
+                    document,
+                    feature,
+                });
+            }
+        }
+        if !dp.get_label().is_finite() {
+            issues.push(Issue::NonFiniteLabel {
+                query: query.clone(),
+                document,
+            });
+        }
+
+        // Documents are duplicates if they share their id or, without ids, their content.
+        // Only the non-zero entries are hashed, so sparse rows are never expanded.
+        let first = match dp.get_doc_id() {
+            Some(doc_id) => *doc_ids.entry(doc_id).or_insert(document),
+            None => {
+                let values = features
+                    .iter()
+                    .filter(|(_, value)| *value != 0.0)
+                    .map(|(index, value)| (index, value.to_bits()))
+                    .collect();
+                *contents
+                    .entry((dp.get_label().to_bits(), values))
+                    .or_insert(document)
+            }
+        };
+        if first != document {
+            issues.push(Issue::DuplicateDocument {
+                query: query.clone(),
+                first,
+                duplicate: document,
+            });
+        }
+    }
+
+    if !data_points.iter().any(|dp| is_relevant(dp.get_label())) {
+        issues.push(Issue::NoRelevantDocuments { query });
+    }
+}
+
+impl DataSet {
+    /// Check the `DataSet` before training. See `validate::validate`.
+    pub fn validate(&self) -> ValidationReport {
+        validate(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        datapoint::{DataPoint, FeatureStorage},
+        loader::svmlight::SVMLight,
+        rl,
+    };
+
+    #[test]
+    fn test_validate() {
+        let valid = DataSet::from(vec![
+            rl!((1, "q1", vec![0.1, 0.5]), (0, "q1", vec![0.9, 0.1])),
+            rl!((2, "q2", vec![0.2, 0.3])),
+        ]);
+        assert_eq!(valid.validate(), ValidationReport::default());
+        assert!(valid.validate().check().is_ok());
+
+        let mut with_id = DataPoint::new(1, "q2", vec![0.2, 0.3], None);
+        with_id.set_doc_id("d1");
+        let mut sparse = DataPoint::new(0, "q3", vec![0.2, 0.3], None);
+        sparse.set_feature_storage(FeatureStorage::Sparse);
+        let invalid = DataSet::from(vec![
+            rl!((1, "q1", vec![0.1, f32::NAN]), (0, "q9", vec![0.9])),
+            RankList::new(vec![]),
+            RankList::new(vec![
+                with_id.clone(),
+                DataPoint::new(0, "q2", vec![0.4, 0.3], None),
+                with_id.clone(),
+                with_id,
+            ]),
+            RankList::new(vec![
+                DataPoint::new(0, "q3", vec![0.2, 0.3], None),
+                DataPoint::new(0, "q3", vec![0.2, 0.3], None),
+                sparse,
+            ]),
+        ]);
+        let report = invalid.validate();
+        assert_eq!(
+            report.issues,
+            vec![
+                Issue::MixedQueryIds {
+                    position: 0,
+                    query_ids: vec!["q1".into(), "q9".into()],
+                },
+                Issue::NonFiniteFeature {
+                    query: "q1".to_string(),
+                    document: 0,
+                    feature: 2,
+                },
+                Issue::InconsistentDimensions {
+                    query: "q1".to_string(),
+                    document: 1,
+                    expected: 2,
+                    found: 1,
+                },
+                Issue::EmptyRankList { position: 1 },
+                Issue::DuplicateDocument {
+                    query: "q2".to_string(),
+                    first: 0,
+                    duplicate: 2,
+                },
+                Issue::DuplicateDocument {
+                    query: "q2".to_string(),
+                    first: 0,
+                    duplicate: 3,
+                },
+                Issue::DuplicateDocument {
+                    query: "q3".to_string(),
+                    first: 0,
+                    duplicate: 1,
+                },
+                Issue::DuplicateDocument {
+                    query: "q3".to_string(),
+                    first: 0,
+                    duplicate: 2,
+                },
+                Issue::NoRelevantDocuments {
+                    query: "q3".to_string(),
+                },
+            ]
+        );
+        assert!(!report.is_valid());
+        assert_eq!(report.errors().count(), 2);
+        assert_eq!(report.warnings().count(), 7);
+        assert_eq!(
+            report.check(),
+            Err(LtrError::InvalidDataSet(
+                "RankList 0 mixes the queries q1, q9".to_string()
+            ))
+        );
+        assert!(format!("{}", report).starts_with("2 errors, 7 warnings\nerror: RankList 0"));
+    }
+
+    #[test]
+    fn test_short_svmlight_rows() {
+        // SVM-Light lines may leave out their trailing zero features.
+        let dataset = SVMLight::load_dataset("1 qid:1 1:0.5 2:0.1\n0 qid:1 1:0.2").unwrap();
+        let report = dataset.validate();
+        assert!(report.is_valid());
+        assert!(report.check().is_ok());
+        assert_eq!(
+            report.warnings().collect::<Vec<_>>(),
+            vec![&Issue::InconsistentDimensions {
+                query: "1".to_string(),
+                document: 1,
+                expected: 2,
+                found: 1,
+            }]
+        );
+    }
+}